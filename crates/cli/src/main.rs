@@ -1,26 +1,78 @@
 use std::fmt::Debug;
+use std::io::{self, BufRead, BufReader};
+use std::str::FromStr;
 use std::string::String;
+use std::time::{Duration, Instant, SystemTime};
 
-use clap::{arg, Parser};
+use clap::builder::{PossibleValuesParser, TypedValueParser};
+use clap::{ArgGroup, Parser};
 use console::style;
 
-use recon::{run, InputArgsBuilder};
+use futures::StreamExt;
+use recon::{
+    run_stream, DnsProtocol, InputArgsBuilder, PermutationSettings, ReconEvent, ReconReport,
+    WildcardDnsMode, WordListTier,
+};
 
-use crate::writer::{CsvWriter, DomainOnlyStdWriter, IPOnlyStdWriter, PlainStdWriter, Writer};
+use crate::progress::Progress;
+use crate::writer::{
+    CsvColumn, CsvOptions, CsvWriter, DomainOnlyStdWriter, IPOnlyStdWriter, JsonLinesWriter,
+    JsonWriter, Output, PlainStdWriter, StreamWriter, Writer,
+};
 
+mod progress;
 mod writer;
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
+#[clap(group(ArgGroup::new("words").multiple(true).args(["file", "builtin_words"])))]
 struct ReconArgs {
     /// Domain name to be scanned
-    #[clap(short, long, value_parser)]
-    domain: String,
+    #[clap(short, long, value_parser, required_unless_present = "domains_file")]
+    domain: Option<String>,
+
+    /// Path to a file with a domain name to be scanned on every line, or "-" for reading them from
+    /// the standard input. Empty lines and lines starting with "#" are skipped. Can be combined
+    /// with --domain.
+    #[clap(long, value_parser)]
+    domains_file: Option<String>,
 
     /// Optional path to a words file used for expand wildcard domains. If there is no path
-    /// provided, there will be no attempt to expand wildcard domains.
-    #[clap(short, long, value_parser)]
-    file: Option<String>,
+    /// provided and no built-in word list, there will be no attempt to expand wildcard domains.
+    /// Can be repeated or contain multiple values delimited by comma, the files being read one
+    /// after the other. Files compressed with gzip or zstd are decompressed on the fly.
+    #[clap(short, long, use_value_delimiter = true, value_delimiter = ',')]
+    file: Vec<String>,
+
+    /// Word list built into the binary, used for expanding wildcard domains together with the
+    /// words files.
+    #[clap(
+        long,
+        ignore_case = true,
+        value_parser = enum_parser::<WordListTier>(&["small", "medium", "large"])
+    )]
+    builtin_words: Option<WordListTier>,
+
+    /// Number of levels expanded with the word list. The words which resolved on a level are
+    /// expanded again on the next one, ex. web.eu.apps.example.com from *.apps.example.com on the
+    /// second level.
+    #[arg(long, default_value_t = 1, requires = "words")]
+    expand_depth: usize,
+
+    /// Maximum number of candidates expanded with the word list for every scanned domain, on all
+    /// levels together.
+    #[clap(long, value_parser, requires = "words")]
+    max_expand_candidates: Option<usize>,
+
+    /// Brute force the scanned domain with the word list, ex. word.example.com, even if the
+    /// certificates contain no wildcard domains.
+    #[clap(long, action, default_value = "false", requires = "words")]
+    brute_force: bool,
+
+    /// Brute force every domain resolved from the certificates with the word list as well, ex.
+    /// word.api.example.com.
+    #[clap(long, action, default_value = "false", requires = "words")]
+    brute_force_subdomains: bool,
 
     /// Display results in plain form (no banner, no color)
     #[clap(
@@ -33,7 +85,7 @@ struct ReconArgs {
     )]
     plain: bool,
 
-    /// Display a plain list with domain names only
+    /// Display a plain list with the names of the resolved domains only
     #[clap(long, action, default_value = "false", conflicts_with = "ips_only")]
     domains_only: bool,
 
@@ -41,17 +93,47 @@ struct ReconArgs {
     #[clap(long, action, default_value = "false")]
     ips_only: bool,
 
-    /// Save output to csv.
+    /// Save output to csv, to the given file or to "result.csv" if no path is given.
+    #[clap(long, value_name = "PATH", num_args = 0..=1, default_missing_value = "result.csv")]
+    csv: Option<String>,
+
+    /// Columns of the csv output, delimited by comma. "first-seen" is the earliest start of
+    /// validity among the certificates the domain was found in. Default is name,record-type,ips,
+    /// "target" tells apart the domains of multiple targets.
+    #[clap(long, value_enum, use_value_delimiter = true, value_delimiter = ',')]
+    csv_columns: Option<Vec<CsvColumn>>,
+
+    /// Write a separate csv row for every IP address of a domain.
+    #[clap(long, action, default_value = "false")]
+    csv_row_per_ip: bool,
+
+    /// Append to the csv file instead of overwriting it. The header row is written only to a new
+    /// or empty file, the header of an existing file must match the columns.
     #[clap(long, action, default_value = "false")]
-    csv: bool,
+    csv_append: bool,
+
+    /// Write the results as a single JSON document, including the metadata of the scan. Written
+    /// to the given file, or to the standard output if no path or "-" is given. An interrupted
+    /// scan writes the results found so far.
+    #[clap(long, value_name = "PATH", num_args = 0..=1, default_missing_value = "-")]
+    json: Option<String>,
+
+    /// Write the results as JSON Lines, one object per domain. Written to the given file, or to
+    /// the standard output if no path or "-" is given.
+    #[clap(long, value_name = "PATH", num_args = 0..=1, default_missing_value = "-")]
+    jsonl: Option<String>,
 
     /// Use default system DNS resolver.
     #[clap(long, action, default_value = "false")]
     use_system_resolver: bool,
 
-    /// Specify a remote DNS resolver. Allowed values are: google, cloudflare, quad9. Default is
-    /// google .Can contain multiple values delimited by comma,
-    /// ex --dns-resolver="google,cloudflare,quad9"
+    /// Specify a remote DNS resolver. Allowed values are: google, cloudflare, quad9, the address of
+    /// a nameserver as ip[:port], optionally prefixed with its protocol (udp:// or tcp://), a DNS
+    /// over HTTPS resolver (google-doh, cloudflare-doh, quad9-doh or an https:// URL), a DNS over
+    /// TLS resolver (google-dot, cloudflare-dot, quad9-dot or tls://host[:port]), or the path to a
+    /// file with a nameserver address on every line. Encrypted resolvers are tried first, the
+    /// others are used as a fallback. Default is google .Can contain multiple values delimited by
+    /// comma, ex --dns-resolver="google,10.0.0.2,tcp://10.0.0.3:5353"
     #[clap(
         long,
         use_value_delimiter = true,
@@ -60,6 +142,66 @@ struct ReconArgs {
     )]
     dns_resolver: Vec<String>,
 
+    /// Protocol used for querying the DNS resolvers which are not given with their own protocol.
+    #[clap(
+        long,
+        default_value = "udp",
+        ignore_case = true,
+        value_parser = enum_parser::<DnsProtocol>(&["udp", "tcp"])
+    )]
+    dns_protocol: DnsProtocol,
+
+    /// Additional DNS record types queried for every resolved domain. Allowed values are: cname,
+    /// mx, ns, txt, caa, soa. Can contain multiple values delimited by comma,
+    /// ex --record-type=cname,mx
+    #[clap(long, use_value_delimiter = true, value_delimiter = ',')]
+    record_type: Vec<String>,
+
+    /// What to do with the domains whose addresses match the wildcard DNS record of their zone.
+    /// "drop" leaves out the ones expanded from the words file and flags the ones found in
+    /// certificates, "flag" keeps and flags all of them.
+    #[clap(
+        long,
+        default_value = "drop",
+        ignore_case = true,
+        value_parser = enum_parser::<WildcardDnsMode>(&["drop", "flag"])
+    )]
+    wildcard_dns: WildcardDnsMode,
+
+    /// Include the domains found in certificates which could not be resolved, together with the
+    /// status of their lookup (NXDOMAIN, NODATA, SERVFAIL, TIMEOUT, REFUSED or ERROR).
+    #[clap(long, action, default_value = "false")]
+    include_unresolved: bool,
+
+    /// Resolve the names from the certificates which are not under the registrable domain of the
+    /// scanned domain as well. By default these are only listed as out of scope.
+    #[clap(long, action, default_value = "false")]
+    no_strict_scope: bool,
+
+    /// Patterns of names resolved even if they are not under the registrable domain of the
+    /// scanned domain. "*" matches any characters. Can contain multiple values delimited by comma,
+    /// ex --scope-include="*.example-cdn.net"
+    #[clap(long, use_value_delimiter = true, value_delimiter = ',')]
+    scope_include: Vec<String>,
+
+    /// Patterns of names which are never resolved. "*" matches any characters. Can contain
+    /// multiple values delimited by comma, ex --scope-exclude="*.dev.example.com"
+    #[clap(long, use_value_delimiter = true, value_delimiter = ',')]
+    scope_exclude: Vec<String>,
+
+    /// Resolve the permutations of the resolved domains, ex. dev-api, api-dev, api2 or
+    /// staging-api for api and dev-api.
+    #[clap(long, action, default_value = "false")]
+    permutations: bool,
+
+    /// Optional path to a words file used for the permutations instead of the built-in words.
+    #[clap(long, value_parser, requires = "permutations")]
+    permutation_words: Option<String>,
+
+    /// Maximum number of permutations resolved for every scanned domain.
+    #[arg(long, default_value_t = 10000, requires = "permutations")]
+    max_permutations: usize,
+
     /// Certificate provider. Allowed values are: certsh, censys, certspotter. Default is certsh.
     /// Can contain multiple values delimited by comma, ex --provider=certsh,censys,certspotter
     #[clap(
@@ -75,9 +217,42 @@ struct ReconArgs {
     #[clap(short, long, value_parser)]
     config: Option<String>,
 
-    /// Number of maximum parallel requests when doing DNS resolution.
+    /// Number of maximum parallel requests when doing DNS resolution, shared by all the domains
+    /// scanned at the same time.
     #[arg(short, long, default_value_t = 10)]
     number_of_parallel_requests: usize,
+
+    /// Number of domains scanned at the same time when scanning multiple domains.
+    #[arg(long, default_value_t = 4)]
+    parallel_targets: usize,
+
+    /// Proxy used for every request sent to the certificate providers. HTTP, HTTPS and SOCKS5
+    /// proxies are supported, ex. --proxy="socks5://127.0.0.1:1080"
+    #[clap(long, value_parser)]
+    proxy: Option<String>,
+
+    /// Optional path to a PEM file with additional root certificates trusted when connecting to
+    /// the certificate providers and to the encrypted DNS resolvers.
+    #[clap(long, value_parser)]
+    ca_bundle: Option<String>,
+
+    /// User-Agent header sent to the certificate providers.
+    #[clap(long, value_parser)]
+    user_agent: Option<String>,
+
+    /// Timeout in seconds for connecting to a certificate provider.
+    #[clap(long, value_parser)]
+    connect_timeout: Option<u64>,
+
+    /// Timeout in seconds for every read of a response from a certificate provider. A response
+    /// which stalls for longer is abandoned, however long the whole response takes.
+    #[clap(long, value_parser)]
+    read_timeout: Option<u64>,
+
+    /// Optional timeout in seconds for a whole request sent to a certificate provider, including
+    /// reading the response. No limit by default.
+    #[clap(long, value_parser)]
+    request_timeout: Option<u64>,
 }
 
 static BANNER: &str = r#"
@@ -92,48 +267,238 @@ static BANNER: &str = r#"
 
 #[tokio::main]
 async fn main() -> anyhow::Result<(), anyhow::Error> {
-    let args: ReconArgs = ReconArgs::parse();
+    recon_with(ReconArgs::parse()).await
+}
+
+async fn recon_with(args: ReconArgs) -> anyhow::Result<(), anyhow::Error> {
+    let json_output = args.json.as_deref().map(Output::from_arg);
+    let jsonl_output = args.jsonl.as_deref().map(Output::from_arg);
 
-    let display_rich = !args.plain && !args.ips_only && !args.domains_only;
+    if json_output == Some(Output::Stdout) && jsonl_output == Some(Output::Stdout) {
+        return Err(anyhow::anyhow!(
+            "--json and --jsonl can not both write to the standard output!"
+        ));
+    }
+
+    // Anything else written to the standard output would corrupt the JSON written there
+    let json_to_stdout = [&json_output, &jsonl_output]
+        .iter()
+        .any(|output| output.as_ref() == Some(&Output::Stdout));
+    let display_rich = !args.plain && !args.ips_only && !args.domains_only && !json_to_stdout;
 
     if display_rich {
         println!("{}", style(BANNER).cyan().bold());
     }
 
-    let input_args = InputArgsBuilder::new(args.domain)
+    let mut targets: Vec<String> = args.domain.iter().cloned().collect();
+    if let Some(domains_file) = &args.domains_file {
+        targets.extend(read_lines(domains_file)?);
+    }
+
+    let permutations = if args.permutations {
+        let mut settings = PermutationSettings {
+            max_candidates: args.max_permutations,
+            ..PermutationSettings::default()
+        };
+        if let Some(path) = &args.permutation_words {
+            settings.words = read_lines(path)?;
+        }
+        Some(settings)
+    } else {
+        None
+    };
+
+    let input_args = InputArgsBuilder::for_targets(targets)
         .certificate_providers(&args.provider)
-        .file(args.file)
+        .files(&args.file)
+        .builtin_words(args.builtin_words)
+        .expand_depth(args.expand_depth)
+        .max_expand_candidates(args.max_expand_candidates)
+        .brute_force(args.brute_force)
+        .brute_force_subdomains(args.brute_force_subdomains)
         .use_system_resolver(args.use_system_resolver)
         .dns_resolvers(&args.dns_resolver)
-        .silent(!display_rich)
+        .dns_protocol(args.dns_protocol)
+        .record_types(&args.record_type)
+        .wildcard_dns(args.wildcard_dns)
+        .include_unresolved(args.include_unresolved)
+        .strict_scope(!args.no_strict_scope)
+        .include_scope(&args.scope_include)
+        .exclude_scope(&args.scope_exclude)
+        .permutations(permutations)
         .config(args.config)
         .number_of_parallel_requests(args.number_of_parallel_requests)
-        .build();
+        .number_of_parallel_targets(args.parallel_targets)
+        .proxy(args.proxy)
+        .ca_bundle(args.ca_bundle)
+        .user_agent(args.user_agent)
+        .connect_timeout(args.connect_timeout.map(Duration::from_secs))
+        .read_timeout(args.read_timeout.map(Duration::from_secs))
+        .request_timeout(args.request_timeout.map(Duration::from_secs))
+        .build()?;
 
-    let result = run(input_args?).await?;
+    // The same domain may have been given more than once
+    let multiple_targets = input_args.targets().len() > 1;
 
-    let mut writers: Vec<Box<dyn Writer>> = vec![];
+    // Every writer which can be fed domain by domain is created before the scan starts, once the
+    // arguments are known to be valid so that no output file is overwritten for nothing
+    let mut stream_writers: Vec<Box<dyn StreamWriter>> = vec![];
     if !display_rich {
         if args.plain {
-            writers.push(Box::new(PlainStdWriter {}));
+            stream_writers.push(Box::new(PlainStdWriter {
+                with_target: multiple_targets,
+            }));
         }
 
         if args.domains_only {
-            writers.push(Box::new(DomainOnlyStdWriter {}));
+            stream_writers.push(Box::new(DomainOnlyStdWriter::default()));
         }
 
         if args.ips_only {
-            writers.push(Box::new(IPOnlyStdWriter {}));
+            stream_writers.push(Box::new(IPOnlyStdWriter::default()));
+        }
+    }
+
+    if let Some(path) = args.csv {
+        stream_writers.push(Box::new(CsvWriter::new(CsvOptions {
+            path,
+            columns: args
+                .csv_columns
+                .unwrap_or_else(|| vec![CsvColumn::Name, CsvColumn::RecordType, CsvColumn::Ips]),
+            row_per_ip: args.csv_row_per_ip,
+            append: args.csv_append,
+        })?));
+    }
+
+    if let Some(output) = jsonl_output {
+        stream_writers.push(Box::new(JsonLinesWriter::new(output)));
+    }
+
+    let mut writers: Vec<Box<dyn Writer>> = vec![];
+    if let Some(output) = json_output {
+        writers.push(Box::new(JsonWriter::new(output)));
+    }
+
+    let mut progress = Progress::new(display_rich);
+    // Collected from the events, the report written if the scan is interrupted
+    let mut partial = ReconReport {
+        targets: input_args.targets().to_vec(),
+        started_at: Some(SystemTime::now()),
+        ..ReconReport::default()
+    };
+    let started = Instant::now();
+    let mut events = Box::pin(run_stream(input_args));
+    let interrupted = tokio::signal::ctrl_c();
+    tokio::pin!(interrupted);
+    let mut report = None;
+    // The report of the stream does not keep the domains, they are collected only if needed
+    let mut domains = vec![];
+    loop {
+        let event = tokio::select! {
+            event = events.next() => event,
+            _ = &mut interrupted => {
+                eprintln!("Interrupted, the results found so far are kept.");
+                partial.duration = started.elapsed();
+                report = Some(partial);
+                break;
+            }
+        };
+        let Some(event) = event else {
+            break;
+        };
+
+        progress.show(&event);
+        match event {
+            ReconEvent::ProviderFinished(summary) => partial.providers.push(summary),
+            ReconEvent::OutOfScope(out_of_scope) => partial.out_of_scope.push(out_of_scope),
+            ReconEvent::Error(error) => partial.errors.push(error),
+            ReconEvent::Warning(warning) => partial.warnings.push(warning),
+            ReconEvent::DomainResolved(domain) | ReconEvent::DomainUnresolved(domain) => {
+                for writer in stream_writers.iter_mut() {
+                    writer.write_domain(&domain)?;
+                }
+                if !writers.is_empty() {
+                    domains.push(domain);
+                }
+            }
+            ReconEvent::Finished(finished) => report = Some(finished),
+            ReconEvent::Failed(error) => return Err(error.into()),
+            _ => {}
         }
     }
 
-    if args.csv {
-        writers.push(Box::new(CsvWriter::new(String::from("result.csv"))));
+    for writer in stream_writers.iter_mut() {
+        writer.finish()?;
     }
 
-    for writer in writers {
-        writer.write(&result)?;
+    if let Some(mut report) = report {
+        report.domains = domains;
+        for writer in writers {
+            writer.write(&report)?;
+        }
     }
 
     Ok(())
 }
+
+// Parser of an enum of the library from one of the given values, which are listed in the help
+fn enum_parser<T>(values: &'static [&'static str]) -> impl TypedValueParser<Value = T>
+where
+    T: FromStr<Err = anyhow::Error> + Clone + Send + Sync + 'static,
+{
+    PossibleValuesParser::new(values).try_map(|value| T::from_str(&value))
+}
+
+// Lines of a file, or of the standard input for "-", without the empty lines and the comments
+fn read_lines(path: &str) -> anyhow::Result<Vec<String>, anyhow::Error> {
+    let reader: Box<dyn BufRead> = match path {
+        "-" => Box::new(BufReader::new(io::stdin())),
+        path => Box::new(BufReader::new(std::fs::File::open(path).map_err(|e| {
+            anyhow::anyhow!("Could not read file \"{}\": {}", path, e)
+        })?)),
+    };
+    let mut targets = vec![];
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if !line.is_empty() && !line.starts_with('#') {
+            targets.push(line.to_string());
+        }
+    }
+    Ok(targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_invalid_args_keep_csv() {
+        let path = std::env::temp_dir().join(format!("{}-kept.csv", std::process::id()));
+        let path = path.to_string_lossy().to_string();
+        let content = "name,record_type,ips\nwww.example.com,A,192.0.2.1\n";
+        std::fs::write(&path, content).expect("Error");
+
+        // Rejected by the arguments, then by the missing config once the scan started
+        for invalid in [
+            vec!["--provider", "nope"],
+            vec!["--provider", "censys", "--config", "/does/not/exist.json"],
+        ] {
+            let args = ReconArgs::parse_from(
+                [
+                    "domain-recon",
+                    "-d",
+                    "example.com",
+                    "--plain",
+                    "--csv",
+                    &path,
+                ]
+                .into_iter()
+                .chain(invalid),
+            );
+            assert!(recon_with(args).await.is_err());
+            assert_eq!(content, std::fs::read_to_string(&path).expect("Error"));
+        }
+        std::fs::remove_file(path).expect("Error");
+    }
+}
@@ -0,0 +1,128 @@
+use console::{style, Emoji};
+use recon::{DomainInfo, LookupStatus, ReconEvent, Stage};
+
+static LOOKING_GLASS: Emoji<'_, '_> = Emoji("🔍  ", "*");
+static CLIP: Emoji<'_, '_> = Emoji("🔗  ", "*");
+static SPARKLE: Emoji<'_, '_> = Emoji("✨ ", "*");
+
+// Number of out of scope names listed, the rest of them is only counted
+const OUT_OF_SCOPE_LISTED: usize = 10;
+
+/// Renders the events of a run on the terminal.
+pub struct Progress {
+    rich: bool,
+    steps: usize,
+}
+
+impl Progress {
+    /// Progress banners, provider summaries, resolved domains and warnings are displayed only if
+    /// `rich` is set. Errors are always displayed, on the standard error if `rich` is not set.
+    pub fn new(rich: bool) -> Progress {
+        Progress { rich, steps: 0 }
+    }
+
+    pub fn show(&mut self, event: &ReconEvent) {
+        match event {
+            // Keep the standard output clean for the results when these are not displayed richly
+            ReconEvent::Error(error) if self.rich => println!("{}", error),
+            ReconEvent::Error(error) => eprintln!("{}", error),
+            _ if !self.rich => {}
+            ReconEvent::StageStarted {
+                target,
+                stage,
+                step,
+                steps,
+            } => {
+                self.steps = *steps;
+                let (emoji, message) = match stage {
+                    Stage::Fetch => (LOOKING_GLASS, "Fetching certificates for"),
+                    Stage::Expand => (SPARKLE, "Expanding wildcards of"),
+                    Stage::Permute => (SPARKLE, "Permuting the domains of"),
+                    _ => (CLIP, "Extracting valid domains of"),
+                };
+                if *step > 1 {
+                    println!();
+                }
+                println!(
+                    "{} {}{}",
+                    style(format!("[{}/{}]", step, steps)).bold().dim(),
+                    emoji,
+                    style(format!("{} {}...", message, target)).bold()
+                );
+            }
+            ReconEvent::ProviderFinished(summary) => println!(
+                "{} {}",
+                style(&summary.provider).bold(),
+                style(format!(
+                    "{} domain(s) for {} from {} page(s)",
+                    summary.names, summary.target, summary.pages
+                ))
+                .dim()
+            ),
+            ReconEvent::OutOfScope(out_of_scope) => {
+                let mut listed = out_of_scope
+                    .names
+                    .iter()
+                    .take(OUT_OF_SCOPE_LISTED)
+                    .cloned()
+                    .collect::<Vec<String>>();
+                if out_of_scope.names.len() > OUT_OF_SCOPE_LISTED {
+                    listed.push(format!(
+                        "and {} more",
+                        out_of_scope.names.len() - OUT_OF_SCOPE_LISTED
+                    ));
+                }
+                println!(
+                    "{} {}",
+                    style(format!(
+                        "{} name(s) out of scope for {}:",
+                        out_of_scope.names.len(),
+                        out_of_scope.target
+                    ))
+                    .bold(),
+                    style(listed.join(", ")).dim()
+                )
+            }
+            ReconEvent::NoDomainsFound(target) => println!(
+                "\n{} {}{}",
+                style(format!("[!/{}]", self.steps)).bold().dim(),
+                CLIP,
+                style(format!("No domains found for {}.", target)).bold()
+            ),
+            ReconEvent::DomainResolved(domain) | ReconEvent::DomainUnresolved(domain) => {
+                pretty_print(domain)
+            }
+            ReconEvent::Warning(warning) => println!("{}", style(warning).dim()),
+            _ => {}
+        }
+    }
+}
+
+fn pretty_print(domain: &DomainInfo) {
+    if domain.status != LookupStatus::Resolved {
+        println!(
+            "{} {}",
+            style(&domain.name).dim(),
+            style(domain.status).red().bold()
+        );
+        return;
+    }
+    println!(
+        "{} {} {}{}",
+        style(&domain.name).green(),
+        style(&domain.domain_type).blue().bold(),
+        style(domain.ip_addresses.join(", ")).magenta().bright(),
+        if domain.wildcard_dns {
+            style(" (wildcard DNS)").yellow().to_string()
+        } else {
+            String::new()
+        }
+    );
+    for (record_type, values) in &domain.records {
+        println!(
+            "  {} {}",
+            style(record_type).blue(),
+            style(values.join(", ")).dim()
+        );
+    }
+}
@@ -1,74 +1,715 @@
-use recon::DomainInfo;
-use std::collections::HashSet;
+use anyhow::anyhow;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use clap::ValueEnum;
+use recon::{
+    CertificateRecord, DnsRecordType, DomainInfo, LookupStatus, OutOfScope, ProviderSummary,
+    ReconError, ReconReport,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::time::UNIX_EPOCH;
 
+/// Version of the JSON and JSON Lines schema. Bumped on every change which is not backwards
+/// compatible, adding a field is not such a change.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Writer of the report of a finished scan.
 pub trait Writer {
-    fn write(&self, domains: &Vec<DomainInfo>) -> anyhow::Result<(), anyhow::Error>;
+    fn write(&self, report: &ReconReport) -> anyhow::Result<(), anyhow::Error>;
+}
+
+/// Writer fed with every domain as soon as it is resolved, so that the results are available while
+/// the scan is running and are kept if the scan is interrupted.
+pub trait StreamWriter {
+    fn write_domain(&mut self, domain: &DomainInfo) -> anyhow::Result<(), anyhow::Error>;
+
+    /// Called once the scan finished or was interrupted.
+    fn finish(&mut self) -> anyhow::Result<(), anyhow::Error> {
+        Ok(())
+    }
+}
+
+/// Destination of a writer, either the standard output or a file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Output {
+    Stdout,
+    File(String),
+}
+
+impl Output {
+    /// The standard output is selected with `-`.
+    pub fn from_arg(arg: &str) -> Output {
+        match arg {
+            "-" => Output::Stdout,
+            path => Output::File(path.to_string()),
+        }
+    }
+
+    fn open(&self) -> anyhow::Result<Box<dyn Write>, anyhow::Error> {
+        Ok(match self {
+            Output::Stdout => Box::new(BufWriter::new(io::stdout())),
+            Output::File(path) => Box::new(BufWriter::new(File::create(path)?)),
+        })
+    }
 }
 
+/// Column of the CSV output.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum CsvColumn {
+    /// Domain which was scanned when the domain was found.
+    Target,
+    Name,
+    RecordType,
+    Ips,
+    Providers,
+    /// Earliest start of validity among the certificates the domain was found in.
+    FirstSeen,
+    /// Additional DNS records, ex. "CNAME cdn.example.net.; MX 10 mail.example.com.".
+    Records,
+    /// Whether the addresses match the wildcard DNS record of the zone.
+    WildcardDns,
+    /// Status of the lookup, ex. "RESOLVED" or "NXDOMAIN".
+    Status,
+}
+
+impl CsvColumn {
+    fn header(&self) -> &'static str {
+        match self {
+            CsvColumn::Target => "target",
+            CsvColumn::Name => "name",
+            CsvColumn::RecordType => "record_type",
+            CsvColumn::Ips => "ips",
+            CsvColumn::Providers => "providers",
+            CsvColumn::FirstSeen => "first_seen",
+            CsvColumn::Records => "records",
+            CsvColumn::WildcardDns => "wildcard_dns",
+            CsvColumn::Status => "status",
+        }
+    }
+
+    fn value(&self, domain: &DomainInfo, ips: &[String]) -> String {
+        match self {
+            CsvColumn::Target => domain.target.clone(),
+            CsvColumn::Name => domain.name.clone(),
+            CsvColumn::RecordType => domain.domain_type.clone(),
+            CsvColumn::Ips => ips.join(", "),
+            CsvColumn::Providers => domain.providers.join(", "),
+            CsvColumn::FirstSeen => domain
+                .certificates
+                .iter()
+                .filter_map(|certificate| certificate.not_before.as_deref())
+                .filter_map(|not_before| Some((parse_date(not_before)?, not_before)))
+                .min()
+                .map(|(_, not_before)| not_before)
+                .unwrap_or_default()
+                .to_string(),
+            CsvColumn::Records => format_records(domain, "; "),
+            CsvColumn::WildcardDns => domain.wildcard_dns.to_string(),
+            CsvColumn::Status => domain.status.to_string(),
+        }
+    }
+}
+
+// Providers return dates in different formats, ex. "2024-01-01T00:00:00Z" or "2024-01-01T00:00:00"
+// which is in UTC as well
+fn parse_date(date: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(date)
+        .map(|date| date.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+                .iter()
+                .find_map(|format| NaiveDateTime::parse_from_str(date, format).ok())
+                .map(|date| date.and_utc())
+        })
+}
+
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    pub path: String,
+    pub columns: Vec<CsvColumn>,
+    /// Write a separate row for every IP address of a domain.
+    pub row_per_ip: bool,
+    /// Append to the file instead of overwriting it. The header is written only to a new or
+    /// empty file, the header of an existing file must match the columns.
+    pub append: bool,
+}
+
+/// Writes a row for every domain. The file is opened on the first domain, or once the scan
+/// finished, so that a scan failing before any result leaves an existing file untouched.
 pub struct CsvWriter {
-    path: String,
+    options: CsvOptions,
+    writer: Option<csv::Writer<File>>,
 }
 
 impl CsvWriter {
-    pub fn new(path: String) -> CsvWriter {
-        CsvWriter { path }
+    pub fn new(options: CsvOptions) -> anyhow::Result<CsvWriter, anyhow::Error> {
+        let header = header(&options.columns);
+        if options.append && std::fs::metadata(&options.path).is_ok_and(|file| file.len() > 0) {
+            let existing = read_header(&options.path)?;
+            if existing != header {
+                return Err(anyhow!(
+                    "The header of \"{}\" is \"{}\" instead of \"{}\", can not append to it!",
+                    options.path,
+                    existing.join(","),
+                    header.join(",")
+                ));
+            }
+        }
+        Ok(CsvWriter {
+            options,
+            writer: None,
+        })
+    }
+
+    fn writer(&mut self) -> anyhow::Result<&mut csv::Writer<File>, anyhow::Error> {
+        if self.writer.is_none() {
+            let file = OpenOptions::new()
+                .write(true)
+                .create(true)
+                .append(self.options.append)
+                .truncate(!self.options.append)
+                .open(&self.options.path)?;
+            let write_header = file.metadata()?.len() == 0;
+            let mut writer = csv::Writer::from_writer(file);
+            if write_header {
+                writer.write_record(header(&self.options.columns))?;
+            }
+            self.writer = Some(writer);
+        }
+        Ok(self.writer.as_mut().expect("The writer was just opened"))
     }
 }
 
-impl Writer for CsvWriter {
-    fn write(&self, domains: &Vec<DomainInfo>) -> anyhow::Result<(), anyhow::Error> {
-        let mut writer = csv::Writer::from_path(&self.path)?;
-        for domain in domains {
-            writer.write_record(&[
-                &domain.name,
-                &domain.domain_type,
-                &domain.ip_addresses.join(", "),
-            ])?;
+fn header(columns: &[CsvColumn]) -> Vec<&'static str> {
+    columns.iter().map(|column| column.header()).collect()
+}
+
+/// Columns of the header row of an existing csv file.
+fn read_header(path: &str) -> anyhow::Result<Vec<String>, anyhow::Error> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_path(path)?;
+    let mut record = csv::StringRecord::new();
+    reader.read_record(&mut record)?;
+    Ok(record.iter().map(|column| column.to_string()).collect())
+}
+
+impl StreamWriter for CsvWriter {
+    fn write_domain(&mut self, domain: &DomainInfo) -> anyhow::Result<(), anyhow::Error> {
+        let row = |ips: &[String]| {
+            self.options
+                .columns
+                .iter()
+                .map(|column| column.value(domain, ips))
+                .collect::<Vec<String>>()
+        };
+        let rows = if self.options.row_per_ip && !domain.ip_addresses.is_empty() {
+            domain
+                .ip_addresses
+                .iter()
+                .map(|ip| row(std::slice::from_ref(ip)))
+                .collect()
+        } else {
+            vec![row(&domain.ip_addresses)]
+        };
+        let writer = self.writer()?;
+        for row in rows {
+            writer.write_record(row)?;
         }
         writer.flush()?;
         Ok(())
     }
+
+    // Creates the file with its header if no domain was found
+    fn finish(&mut self) -> anyhow::Result<(), anyhow::Error> {
+        self.writer()?.flush()?;
+        Ok(())
+    }
 }
 
-pub struct PlainStdWriter {}
+/// Additional DNS records of a domain, every record prefixed with its type.
+pub fn format_records(domain: &DomainInfo, separator: &str) -> String {
+    domain
+        .records
+        .iter()
+        .flat_map(|(record_type, values)| {
+            values
+                .iter()
+                .map(move |value| format!("{} {}", record_type, value))
+        })
+        .collect::<Vec<String>>()
+        .join(separator)
+}
 
-impl Writer for PlainStdWriter {
-    fn write(&self, domains: &Vec<DomainInfo>) -> anyhow::Result<(), anyhow::Error> {
-        for domain in domains {
+pub struct PlainStdWriter {
+    /// Prefix every line with the target, when scanning multiple domains.
+    pub with_target: bool,
+}
+
+impl StreamWriter for PlainStdWriter {
+    fn write_domain(&mut self, domain: &DomainInfo) -> anyhow::Result<(), anyhow::Error> {
+        if self.with_target {
+            print!("{} ", domain.target);
+        }
+        if domain.status != LookupStatus::Resolved {
+            println!("{} {}", domain.name, domain.status);
+        } else if domain.records.is_empty() {
             println!(
                 "{} {} {}",
                 domain.name,
                 domain.domain_type,
                 domain.ip_addresses.join(",")
-            )
+            );
+        } else {
+            println!(
+                "{} {} {} {}",
+                domain.name,
+                domain.domain_type,
+                domain.ip_addresses.join(","),
+                format_records(domain, ",")
+            );
         }
         Ok(())
     }
 }
 
-pub struct DomainOnlyStdWriter {}
+/// Lists the names of the resolved domains. The unresolved ones are left out, as they could not
+/// be told apart from the resolved ones.
+pub struct DomainOnlyStdWriter<W: Write = io::Stdout> {
+    out: W,
+}
+
+impl Default for DomainOnlyStdWriter {
+    fn default() -> DomainOnlyStdWriter {
+        DomainOnlyStdWriter { out: io::stdout() }
+    }
+}
 
-impl Writer for DomainOnlyStdWriter {
-    fn write(&self, domains: &Vec<DomainInfo>) -> anyhow::Result<(), anyhow::Error> {
-        for domain in domains {
-            println!("{}", domain.name)
+impl<W: Write> StreamWriter for DomainOnlyStdWriter<W> {
+    fn write_domain(&mut self, domain: &DomainInfo) -> anyhow::Result<(), anyhow::Error> {
+        if domain.status == LookupStatus::Resolved {
+            writeln!(self.out, "{}", domain.name)?;
         }
         Ok(())
     }
 }
 
-pub struct IPOnlyStdWriter {}
+/// Lists every IP address of the resolved domains once.
+pub struct IPOnlyStdWriter<W: Write = io::Stdout> {
+    out: W,
+    seen: HashSet<String>,
+}
 
-impl Writer for IPOnlyStdWriter {
-    fn write(&self, domains: &Vec<DomainInfo>) -> anyhow::Result<(), anyhow::Error> {
-        let uniq_ips: HashSet<String> = HashSet::from_iter(
-            domains
-                .into_iter()
-                .flat_map(|domain_info| domain_info.ip_addresses.clone()),
-        );
-        for ip in uniq_ips {
-            println!("{}", ip)
+impl Default for IPOnlyStdWriter {
+    fn default() -> IPOnlyStdWriter {
+        IPOnlyStdWriter {
+            out: io::stdout(),
+            seen: HashSet::new(),
+        }
+    }
+}
+
+impl<W: Write> StreamWriter for IPOnlyStdWriter<W> {
+    fn write_domain(&mut self, domain: &DomainInfo) -> anyhow::Result<(), anyhow::Error> {
+        if domain.status != LookupStatus::Resolved {
+            return Ok(());
+        }
+        for ip in &domain.ip_addresses {
+            if self.seen.insert(ip.clone()) {
+                writeln!(self.out, "{}", ip)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+struct JsonDomain<'a> {
+    target: &'a str,
+    name: &'a str,
+    record_type: &'a str,
+    ip_addresses: &'a [String],
+    providers: &'a [String],
+    certificates: &'a [CertificateRecord],
+    records: &'a BTreeMap<DnsRecordType, Vec<String>>,
+    wildcard_dns: bool,
+    status: LookupStatus,
+}
+
+impl<'a> From<&'a DomainInfo> for JsonDomain<'a> {
+    fn from(domain: &'a DomainInfo) -> JsonDomain<'a> {
+        JsonDomain {
+            target: &domain.target,
+            name: &domain.name,
+            record_type: &domain.domain_type,
+            ip_addresses: &domain.ip_addresses,
+            providers: &domain.providers,
+            certificates: &domain.certificates,
+            records: &domain.records,
+            wildcard_dns: domain.wildcard_dns,
+            status: domain.status,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonIssue<'a> {
+    stage: String,
+    target: Option<&'a str>,
+    provider: Option<&'a str>,
+    message: String,
+}
+
+impl<'a> From<&'a ReconError> for JsonIssue<'a> {
+    fn from(error: &'a ReconError) -> JsonIssue<'a> {
+        JsonIssue {
+            stage: error.stage().to_string(),
+            target: error.target(),
+            provider: error.provider(),
+            message: error.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    schema_version: u32,
+    tool: &'static str,
+    tool_version: &'static str,
+    targets: &'a [String],
+    /// Start of the scan in seconds since the Unix epoch
+    started_at: Option<u64>,
+    duration_ms: u128,
+    providers: &'a [ProviderSummary],
+    errors: Vec<JsonIssue<'a>>,
+    warnings: Vec<JsonIssue<'a>>,
+    domains: Vec<JsonDomain<'a>>,
+    /// Names from the certificates left out by the scope
+    out_of_scope: &'a [OutOfScope],
+}
+
+/// Writes the whole report as a single JSON document, including the metadata of the scan.
+pub struct JsonWriter {
+    output: Output,
+}
+
+impl JsonWriter {
+    pub fn new(output: Output) -> JsonWriter {
+        JsonWriter { output }
+    }
+}
+
+impl<'a> JsonReport<'a> {
+    fn new(report: &'a ReconReport) -> JsonReport<'a> {
+        JsonReport {
+            schema_version: SCHEMA_VERSION,
+            tool: env!("CARGO_PKG_NAME"),
+            tool_version: env!("CARGO_PKG_VERSION"),
+            targets: &report.targets,
+            started_at: report
+                .started_at
+                .and_then(|started_at| started_at.duration_since(UNIX_EPOCH).ok())
+                .map(|since_epoch| since_epoch.as_secs()),
+            duration_ms: report.duration.as_millis(),
+            providers: &report.providers,
+            errors: report.errors.iter().map(JsonIssue::from).collect(),
+            warnings: report.warnings.iter().map(JsonIssue::from).collect(),
+            domains: report.domains.iter().map(JsonDomain::from).collect(),
+            out_of_scope: &report.out_of_scope,
         }
+    }
+}
+
+impl Writer for JsonWriter {
+    fn write(&self, report: &ReconReport) -> anyhow::Result<(), anyhow::Error> {
+        let document = JsonReport::new(report);
+        let mut out = self.output.open()?;
+        serde_json::to_writer_pretty(&mut out, &document)?;
+        writeln!(out)?;
+        out.flush()?;
         Ok(())
     }
 }
+
+#[derive(Serialize)]
+struct JsonLine<'a> {
+    schema_version: u32,
+    #[serde(flatten)]
+    domain: JsonDomain<'a>,
+}
+
+impl<'a> JsonLine<'a> {
+    fn new(domain: &'a DomainInfo) -> JsonLine<'a> {
+        JsonLine {
+            schema_version: SCHEMA_VERSION,
+            domain: JsonDomain::from(domain),
+        }
+    }
+}
+
+/// Writes one JSON object per line for every domain. Like the csv file, the output is opened on
+/// the first domain or once the scan finished.
+pub struct JsonLinesWriter {
+    output: Output,
+    out: Option<Box<dyn Write>>,
+}
+
+impl JsonLinesWriter {
+    pub fn new(output: Output) -> JsonLinesWriter {
+        JsonLinesWriter { output, out: None }
+    }
+
+    fn out(&mut self) -> anyhow::Result<&mut Box<dyn Write>, anyhow::Error> {
+        if self.out.is_none() {
+            self.out = Some(self.output.open()?);
+        }
+        Ok(self.out.as_mut().expect("The output was just opened"))
+    }
+}
+
+impl StreamWriter for JsonLinesWriter {
+    fn write_domain(&mut self, domain: &DomainInfo) -> anyhow::Result<(), anyhow::Error> {
+        let out = self.out()?;
+        serde_json::to_writer(&mut *out, &JsonLine::new(domain))?;
+        writeln!(out)?;
+        out.flush()?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> anyhow::Result<(), anyhow::Error> {
+        self.out()?.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use recon::Stage;
+    use serde_json::json;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn domain() -> DomainInfo {
+        DomainInfo::new(
+            String::from("www.example.com"),
+            String::from("A"),
+            vec![String::from("192.0.2.1")],
+        )
+        .with_certificates(vec![CertificateRecord {
+            provider: String::from("certsh"),
+            id: Some(String::from("42")),
+            not_before: Some(String::from("2024-01-01T00:00:00")),
+            ..CertificateRecord::default()
+        }])
+        .with_records(BTreeMap::from([(
+            DnsRecordType::Cname,
+            vec![String::from("cdn.example.net.")],
+        )]))
+        .with_target("example.com")
+    }
+
+    #[test]
+    fn test_plain_lists_skip_unresolved() {
+        let unresolved =
+            DomainInfo::unresolved(String::from("old.example.com"), LookupStatus::NxDomain);
+        let mut domains = DomainOnlyStdWriter { out: vec![] };
+        let mut ips = IPOnlyStdWriter {
+            out: vec![],
+            seen: HashSet::new(),
+        };
+        for domain in [domain(), unresolved, domain()] {
+            domains.write_domain(&domain).expect("Error");
+            ips.write_domain(&domain).expect("Error");
+        }
+        assert_eq!(
+            "www.example.com\nwww.example.com\n",
+            String::from_utf8(domains.out).expect("Error")
+        );
+        assert_eq!("192.0.2.1\n", String::from_utf8(ips.out).expect("Error"));
+    }
+
+    fn domain_json() -> serde_json::Value {
+        json!({
+            "target": "example.com",
+            "name": "www.example.com",
+            "record_type": "A",
+            "ip_addresses": ["192.0.2.1"],
+            "providers": ["certsh"],
+            "certificates": [{
+                "provider": "certsh",
+                "id": "42",
+                "issuer": null,
+                "serial_number": null,
+                "not_before": "2024-01-01T00:00:00",
+                "not_after": null,
+                "fingerprint_sha256": null,
+                "tbs_sha256": null,
+                "revoked": null
+            }],
+            "records": {"CNAME": ["cdn.example.net."]},
+            "wildcard_dns": false,
+            "status": "RESOLVED"
+        })
+    }
+
+    fn csv_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_file(&path);
+        path.to_string_lossy().to_string()
+    }
+
+    fn csv_options(path: &str, columns: &[CsvColumn]) -> CsvOptions {
+        CsvOptions {
+            path: path.to_string(),
+            columns: columns.to_vec(),
+            row_per_ip: false,
+            append: false,
+        }
+    }
+
+    #[test]
+    fn test_csv_columns() {
+        let path = csv_path("columns.csv");
+        let mut domain = domain();
+        domain.ip_addresses.push(String::from("192.0.2.2"));
+        let mut writer = CsvWriter::new(CsvOptions {
+            row_per_ip: true,
+            ..csv_options(
+                &path,
+                &[CsvColumn::Name, CsvColumn::Ips, CsvColumn::Records],
+            )
+        })
+        .expect("Error");
+        writer.write_domain(&domain).expect("Error");
+        assert_eq!(
+            "name,ips,records\n\
+             www.example.com,192.0.2.1,CNAME cdn.example.net.\n\
+             www.example.com,192.0.2.2,CNAME cdn.example.net.\n",
+            std::fs::read_to_string(&path).expect("Error")
+        );
+        std::fs::remove_file(path).expect("Error");
+    }
+
+    #[test]
+    fn test_csv_append() {
+        let path = csv_path("append.csv");
+        let columns = [CsvColumn::Name, CsvColumn::RecordType, CsvColumn::Ips];
+        for _ in 0..2 {
+            let mut writer = CsvWriter::new(CsvOptions {
+                append: true,
+                ..csv_options(&path, &columns)
+            })
+            .expect("Error");
+            writer.write_domain(&domain()).expect("Error");
+        }
+        assert_eq!(
+            "name,record_type,ips\n\
+             www.example.com,A,192.0.2.1\n\
+             www.example.com,A,192.0.2.1\n",
+            std::fs::read_to_string(&path).expect("Error")
+        );
+
+        // Rows with other columns would not match the existing header
+        let other = CsvWriter::new(CsvOptions {
+            append: true,
+            ..csv_options(&path, &[CsvColumn::Target, CsvColumn::Name])
+        });
+        assert!(other.is_err());
+        std::fs::remove_file(path).expect("Error");
+    }
+
+    #[test]
+    fn test_first_seen() {
+        let mut domain = domain();
+        let certificate = |not_before: &str| CertificateRecord {
+            provider: String::from("certspotter"),
+            not_before: Some(not_before.to_string()),
+            ..CertificateRecord::default()
+        };
+        domain.certificates = vec![
+            certificate("2023-06-01T00:00:00Z"),
+            certificate("2023-05-31 23:30:00"),
+            certificate("2023-06-01T01:00:00+02:00"),
+            certificate("unknown"),
+        ];
+        assert_eq!(
+            "2023-06-01T01:00:00+02:00",
+            CsvColumn::FirstSeen.value(&domain, &[])
+        );
+    }
+
+    #[test]
+    fn test_json_line_schema() {
+        let mut expected = domain_json();
+        expected["schema_version"] = json!(SCHEMA_VERSION);
+        assert_eq!(
+            expected,
+            serde_json::to_value(JsonLine::new(&domain())).expect("Error")
+        );
+    }
+
+    #[test]
+    fn test_json_report_schema() {
+        let report = ReconReport {
+            targets: vec![String::from("example.com")],
+            started_at: Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000)),
+            duration: Duration::from_millis(1500),
+            domains: vec![domain()],
+            providers: vec![ProviderSummary {
+                target: String::from("example.com"),
+                provider: String::from("certsh"),
+                names: 1,
+                pages: 1,
+            }],
+            out_of_scope: vec![OutOfScope {
+                target: String::from("example.com"),
+                names: vec![String::from("cdn.example.net")],
+            }],
+            errors: vec![ReconError::ProviderIncomplete {
+                target: String::from("example.com"),
+                provider: String::from("certsh"),
+                message: String::from("HTTP 500"),
+            }],
+            warnings: vec![ReconError::Resolution {
+                target: String::from("example.com"),
+                stage: Stage::Resolve,
+                domain: String::from("api.example.com"),
+                message: String::from("timed out"),
+            }],
+        };
+        assert_eq!(
+            json!({
+                "schema_version": SCHEMA_VERSION,
+                "tool": env!("CARGO_PKG_NAME"),
+                "tool_version": env!("CARGO_PKG_VERSION"),
+                "targets": ["example.com"],
+                "started_at": 1_700_000_000,
+                "duration_ms": 1500,
+                "providers": [{
+                    "target": "example.com",
+                    "provider": "certsh",
+                    "names": 1,
+                    "pages": 1
+                }],
+                "errors": [{
+                    "stage": "fetch",
+                    "target": "example.com",
+                    "provider": "certsh",
+                    "message": "Could not fetch all results for example.com from provider certsh. Error: HTTP 500"
+                }],
+                "warnings": [{
+                    "stage": "resolve",
+                    "target": "example.com",
+                    "provider": null,
+                    "message": "Could not resolve \"api.example.com\": timed out"
+                }],
+                "domains": [domain_json()],
+                "out_of_scope": [{"target": "example.com", "names": ["cdn.example.net"]}]
+            }),
+            serde_json::to_value(JsonReport::new(&report)).expect("Error")
+        );
+    }
+}
@@ -1,7 +1,12 @@
+use crate::certificate_source::{
+    CertificateRecord, CertificateSource, FetchContext, FetchResult, ProviderSettings,
+};
+use crate::credentials::{mask, Credential, Credentials, ProviderHttpError};
+use crate::retry;
+use crate::retry::RetryPolicy;
 use anyhow::anyhow;
-use futures::StreamExt;
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
 use std::fmt::{Debug, Display};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -11,144 +16,277 @@ pub(crate) struct CensysConfig {
     pub(crate) secret: String,
 }
 
-#[allow(dead_code)]
-#[derive(Debug, Serialize, Clone)]
-struct Request {
-    query: String,
-    page: i32,
-    flatten: bool,
-    fields: Vec<String>,
+impl Credential for CensysConfig {
+    fn label(&self) -> String {
+        format!("app-id {}", mask(&self.app_id))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ValidityPeriod {
+    not_before: Option<String>,
+    not_after: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Parsed {
+    issuer_dn: Option<String>,
+    serial_number: Option<String>,
+    validity_period: Option<ValidityPeriod>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Hit {
+    names: Option<Vec<String>>,
+    fingerprint_sha256: Option<String>,
+    parsed: Option<Parsed>,
+}
+
+impl Hit {
+    fn record(&self) -> CertificateRecord {
+        let parsed = self.parsed.as_ref();
+        let validity = parsed.and_then(|parsed| parsed.validity_period.as_ref());
+        CertificateRecord {
+            provider: String::from("censys"),
+            issuer: parsed.and_then(|parsed| parsed.issuer_dn.clone()),
+            serial_number: parsed.and_then(|parsed| parsed.serial_number.clone()),
+            not_before: validity.and_then(|validity| validity.not_before.clone()),
+            not_after: validity.and_then(|validity| validity.not_after.clone()),
+            fingerprint_sha256: self.fingerprint_sha256.clone(),
+            ..CertificateRecord::default()
+        }
+    }
 }
 
 #[allow(dead_code)]
 #[derive(Debug, Deserialize)]
-struct MetaData {
-    query: String,
-    count: i32,
-    backend_time: i32,
-    page: i32,
-    pages: i32,
+struct Links {
+    prev: Option<String>,
+    next: Option<String>,
 }
 
 #[allow(dead_code)]
 #[derive(Debug, Deserialize)]
-struct ParsedResult {
-    #[serde(rename = "parsed.extensions.subject_alt_name.dns_names")]
-    dns_names: Option<Vec<String>>,
-    #[serde(rename = "parsed.names")]
-    names: Option<Vec<String>>,
+struct SearchResult {
+    query: String,
+    total: i64,
+    hits: Vec<Hit>,
+    links: Links,
 }
 
 #[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 struct CensysResponse {
+    code: i32,
     status: String,
-    metadata: MetaData,
-    results: Vec<ParsedResult>,
+    result: SearchResult,
 }
 
 #[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 struct CensysErrorResponse {
+    code: i32,
     status: String,
     error: String,
-    error_type: String,
 }
 
-const MAX_PARALLEL_REQUESTS: usize = 10;
+const CENSYS_URL: &str = "https://search.censys.io";
 
-pub(crate) async fn fetch(
-    domain: String,
-    config: Vec<CensysConfig>,
-) -> anyhow::Result<(Vec<String>, Vec<String>), anyhow::Error> {
-    let CensysConfig { app_id, secret } = &config[0];
+// Maximum number of hits per page accepted by the Search v2 API
+const MAX_PER_PAGE: usize = 100;
 
-    let responses = get_certificates(&domain, app_id, secret).await?;
+#[derive(Debug)]
+pub(crate) struct CensysSource {}
 
-    let all_domains = responses
-        .into_iter()
-        .filter(|response| response.status == "ok")
-        .flat_map(|response| response.results)
-        .flat_map(|parsed_result| {
-            let mut domains = HashSet::<String>::new();
-            domains.extend(match parsed_result.names {
-                Some(ref names) => names.to_vec(),
-                None => vec![],
-            });
-            domains.extend(match parsed_result.dns_names {
-                Some(ref names) => names.to_vec(),
-                None => vec![],
-            });
-            domains
-        })
-        .collect::<HashSet<String>>();
+impl CensysSource {
+    fn parse_config(
+        config: Option<&serde_json::Value>,
+    ) -> anyhow::Result<Vec<CensysConfig>, anyhow::Error> {
+        match config {
+            None => Err(anyhow!(
+                "Censys requires secrets in the configuration file!"
+            )),
+            Some(value) => {
+                let secrets =
+                    serde_json::from_value::<Vec<CensysConfig>>(value.clone()).map_err(|e| {
+                        anyhow!("Invalid Censys secrets in the configuration file: {e}")
+                    })?;
+                if secrets.is_empty() {
+                    return Err(anyhow!("Empty array provided for Censys secrets!"));
+                }
+                for (i, secret) in secrets.iter().enumerate() {
+                    if secret.app_id.trim().is_empty() || secret.secret.trim().is_empty() {
+                        return Err(anyhow!(
+                            "Censys secret #{} requires a non-empty \"app-id\" and \"secret\"!",
+                            i + 1
+                        ));
+                    }
+                }
+                Ok(secrets)
+            }
+        }
+    }
+}
 
-    let (wildcards, fqdns): (Vec<String>, Vec<String>) = all_domains
-        .into_iter()
-        .partition(|domain| domain.starts_with('*'));
+#[async_trait]
+impl CertificateSource for CensysSource {
+    fn name(&self) -> &str {
+        "censys"
+    }
 
-    Ok((wildcards, fqdns))
+    fn config_schema(&self) -> Option<serde_json::Value> {
+        Some(serde_json::json!([{"app-id": "<app-id>", "secret": "<secret>"}]))
+    }
+
+    fn validate(&self, config: Option<&serde_json::Value>) -> anyhow::Result<(), anyhow::Error> {
+        CensysSource::parse_config(config).map(|_| ())
+    }
+
+    async fn fetch(
+        &self,
+        domain: &str,
+        context: FetchContext<'_>,
+    ) -> anyhow::Result<FetchResult, anyhow::Error> {
+        fetch(
+            context.client,
+            domain,
+            CensysSource::parse_config(context.config)?,
+            context.settings,
+        )
+        .await
+    }
 }
 
-async fn get_certificates<S>(
-    domain: S,
-    api_id: S,
-    secret: S,
-) -> anyhow::Result<Vec<CensysResponse>, anyhow::Error>
-where
-    S: AsRef<str> + Display,
-{
-    let client = reqwest::Client::new();
+async fn fetch(
+    client: &reqwest::Client,
+    domain: &str,
+    config: Vec<CensysConfig>,
+    settings: &ProviderSettings,
+) -> anyhow::Result<FetchResult, anyhow::Error> {
+    let (responses, errors) = get_certificates(
+        client,
+        settings.base_url_or(CENSYS_URL),
+        domain,
+        &config,
+        settings,
+    )
+    .await?;
+    let pages = responses.len();
+
+    let mut result = FetchResult::new(pages);
+    for hit in responses
+        .into_iter()
+        .flat_map(|response| response.result.hits)
+    {
+        let record = hit.record();
+        result.add_certificate(hit.names.unwrap_or_default(), record);
+    }
+    result.errors = errors;
+    Ok(result)
+}
 
-    let create_request = |page: i32| Request {
-        query: format!("validation.nss.valid: true and parsed.names: {}", domain),
-        page: page as i32,
-        flatten: true,
-        fields: vec![
-            String::from("parsed.names"),
-            String::from("parsed.extensions.subject_alt_name.dns_names"),
-        ],
-    };
+// Walk the result pages by following the `next` cursor until there are no more pages, or the page
+// or result limit is reached. A failing first page fails the whole fetch, a failing later page
+// stops the pagination and is reported together with the pages fetched so far. Exhausted
+// credentials are reported as well.
+async fn get_certificates(
+    client: &reqwest::Client,
+    base_url: &str,
+    domain: &str,
+    config: &[CensysConfig],
+    settings: &ProviderSettings,
+) -> anyhow::Result<(Vec<CensysResponse>, Vec<String>), anyhow::Error> {
+    let query = format!("names: {}", domain);
+    let mut credentials = Credentials::new("Censys", config);
+    let retry_policy = settings.retry_policy();
 
-    let first_response = send_request(&client, create_request(1), &api_id, &secret).await?;
+    let mut responses: Vec<CensysResponse> = vec![];
+    let mut errors = vec![];
+    let mut cursor: Option<String> = None;
+    let mut hits = 0;
+    loop {
+        let per_page = settings.max_results.map_or(MAX_PER_PAGE, |max| {
+            max.saturating_sub(hits).clamp(1, MAX_PER_PAGE)
+        });
+        let response = credentials
+            .send(|credential, last| {
+                // Wait for the rate limit to pass only if there is no other credential to try
+                send_request(
+                    client,
+                    base_url,
+                    SearchParams {
+                        query: query.as_str(),
+                        per_page,
+                        cursor: cursor.as_deref(),
+                    },
+                    credential,
+                    &retry_policy,
+                    last,
+                )
+            })
+            .await;
 
-    let future_responses = (2..first_response.metadata.pages)
-        .map(|i| send_request(&client, create_request(i), &api_id, &secret))
-        .collect::<Vec<_>>();
+        let response = match response {
+            Ok(response) => response,
+            Err(e) if responses.is_empty() => return Err(e),
+            Err(e) => {
+                errors.push(format!("Page {}: {}", responses.len() + 1, e));
+                break;
+            }
+        };
 
-    let stream = futures::stream::iter(future_responses).buffer_unordered(MAX_PARALLEL_REQUESTS);
-    let results = stream
-        .collect::<Vec<Result<CensysResponse, anyhow::Error>>>()
-        .await;
+        hits += response.result.hits.len();
+        cursor = response
+            .result
+            .links
+            .next
+            .clone()
+            .filter(|next| !next.is_empty());
+        responses.push(response);
 
-    let mut responses = vec![first_response];
-    responses.extend(
-        results
-            .into_iter()
-            .filter(|response| response.is_ok())
-            .map(|response| response.unwrap()),
-    );
+        if cursor.is_none()
+            || settings.max_results.is_some_and(|max| hits >= max)
+            || settings.max_pages.is_some_and(|max| responses.len() >= max)
+        {
+            break;
+        }
+    }
 
-    Ok(responses)
+    errors.splice(0..0, credentials.exhausted());
+    Ok((responses, errors))
+}
+
+struct SearchParams<'a> {
+    query: &'a str,
+    per_page: usize,
+    cursor: Option<&'a str>,
 }
 
 async fn send_request<S>(
     client: &reqwest::Client,
-    request: Request,
-    api_id: S,
-    secret: S,
+    base_url: S,
+    params: SearchParams<'_>,
+    credential: &CensysConfig,
+    retry_policy: &RetryPolicy,
+    wait_on_rate_limit: bool,
 ) -> anyhow::Result<CensysResponse, anyhow::Error>
 where
     S: AsRef<str> + Display,
 {
-    let response = client
-        .post("https://search.censys.io/api/v1/search/certificates")
-        .json(&request)
-        .basic_auth(api_id, Some(secret))
-        .header("Content-Type", "application/json")
-        .header("Accept", "application/json")
-        .send()
-        .await;
+    let mut query = vec![
+        ("q", params.query.to_string()),
+        ("per_page", params.per_page.to_string()),
+    ];
+    if let Some(cursor) = params.cursor {
+        query.push(("cursor", cursor.to_string()));
+    }
+
+    let request = client
+        .get(format!("{}/api/v2/certificates/search", base_url))
+        .query(&query)
+        .basic_auth(&credential.app_id, Some(&credential.secret))
+        .header("Accept", "application/json");
+    let response = retry::send(retry_policy, request, wait_on_rate_limit).await;
 
     match response {
         Ok(response_content) => {
@@ -159,14 +297,181 @@ where
                     .map_err(anyhow::Error::from)
             } else {
                 let code = response_content.status();
-                let error_response = response_content.json::<CensysErrorResponse>().await?;
-                let error_massage = error_response.error;
-                Err(anyhow!(format!(
-                    "Censys responded with HTTP code \"{code}\" and with message of: \"{error_massage}\"\n\
-                    You may want to try other provider!"
-                )))
+                let error_massage = response_content
+                    .json::<CensysErrorResponse>()
+                    .await
+                    .map(|error_response| error_response.error)
+                    .unwrap_or_default();
+                Err(anyhow::Error::from(ProviderHttpError {
+                    status: code,
+                    message: format!(
+                        "Censys responded with HTTP code \"{code}\" and with message of: \"{error_massage}\"\n\
+                        You may want to try other provider!"
+                    ),
+                }))
             }
         }
         Err(err_content) => Err(anyhow!(err_content)),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{basic_auth, method, path, query_param, query_param_is_missing};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn page(names: &[&str], next: &str) -> serde_json::Value {
+        serde_json::json!({
+            "code": 200,
+            "status": "OK",
+            "result": {
+                "query": "names: example.com",
+                "total": 3,
+                "hits": names
+                    .iter()
+                    .map(|name| serde_json::json!({"names": [name], "fingerprint_sha256": "fp"}))
+                    .collect::<Vec<_>>(),
+                "links": {"prev": "", "next": next}
+            }
+        })
+    }
+
+    fn credentials() -> Vec<CensysConfig> {
+        vec![
+            CensysConfig {
+                app_id: String::from("first-id"),
+                secret: String::from("secret"),
+            },
+            CensysConfig {
+                app_id: String::from("second-id"),
+                secret: String::from("secret"),
+            },
+        ]
+    }
+
+    async fn mock_first_page(server: &MockServer) {
+        Mock::given(method("GET"))
+            .and(path("/api/v2/certificates/search"))
+            .and(query_param_is_missing("cursor"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(page(&["a.example.com", "*.example.com"], "c1")),
+            )
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_get_certificates_follows_cursor() {
+        let server = MockServer::start().await;
+        mock_first_page(&server).await;
+        Mock::given(method("GET"))
+            .and(path("/api/v2/certificates/search"))
+            .and(query_param("cursor", "c1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(page(&["b.example.com"], "")))
+            .mount(&server)
+            .await;
+
+        let (responses, errors) = get_certificates(
+            &reqwest::Client::new(),
+            server.uri().as_str(),
+            "example.com",
+            &credentials(),
+            &ProviderSettings::default(),
+        )
+        .await
+        .expect("Error");
+        assert_eq!(2, responses.len());
+        assert!(errors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_certificates_result_limit() {
+        let server = MockServer::start().await;
+        mock_first_page(&server).await;
+
+        let settings = ProviderSettings {
+            max_results: Some(2),
+            ..ProviderSettings::default()
+        };
+        let (responses, errors) = get_certificates(
+            &reqwest::Client::new(),
+            server.uri().as_str(),
+            "example.com",
+            &credentials(),
+            &settings,
+        )
+        .await
+        .expect("Error");
+        assert_eq!(1, responses.len());
+        assert!(errors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_certificates_reports_failed_page() {
+        let server = MockServer::start().await;
+        mock_first_page(&server).await;
+        Mock::given(method("GET"))
+            .and(path("/api/v2/certificates/search"))
+            .and(query_param("cursor", "c1"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let (responses, errors) = get_certificates(
+            &reqwest::Client::new(),
+            server.uri().as_str(),
+            "example.com",
+            &credentials(),
+            &ProviderSettings::default(),
+        )
+        .await
+        .expect("Error");
+        assert_eq!(1, responses.len());
+        assert_eq!(1, errors.len());
+    }
+
+    #[tokio::test]
+    async fn test_get_certificates_fails_over_to_next_credential() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v2/certificates/search"))
+            .and(basic_auth("first-id", "secret"))
+            .respond_with(ResponseTemplate::new(429).set_body_json(serde_json::json!({
+                "code": 429,
+                "status": "Too Many Requests",
+                "error": "Quota exceeded"
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v2/certificates/search"))
+            .and(basic_auth("second-id", "secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(page(&["b.example.com"], "")))
+            .mount(&server)
+            .await;
+
+        let (responses, errors) = get_certificates(
+            &reqwest::Client::new(),
+            server.uri().as_str(),
+            "example.com",
+            &credentials(),
+            &ProviderSettings::default(),
+        )
+        .await
+        .expect("Error");
+        assert_eq!(1, responses.len());
+        assert_eq!(1, errors.len());
+        assert!(errors[0].contains("credential #1"));
+    }
+
+    #[test]
+    fn test_parse_config_rejects_empty_entry() {
+        let config = serde_json::json!([
+            {"app-id": "id", "secret": "secret"},
+            {"app-id": "", "secret": "secret"}
+        ]);
+        assert!(CensysSource::parse_config(Some(&config)).is_err());
+    }
+}
@@ -0,0 +1,302 @@
+use crate::retry::RetryPolicy;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use crate::censys_fetcher::CensysSource;
+use crate::certspotter_fetcher::CertSpotterSource;
+use crate::crtsh_fetcher::CrtShSource;
+
+#[derive(Debug)]
+#[allow(dead_code, unused_variables)]
+pub(crate) struct UnknownCertificateProvider {
+    pub(crate) provider: String,
+}
+
+impl UnknownCertificateProvider {
+    fn new(provider: String) -> UnknownCertificateProvider {
+        UnknownCertificateProvider { provider }
+    }
+}
+
+impl std::fmt::Display for UnknownCertificateProvider {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> anyhow::Result<(), std::fmt::Error> {
+        write!(fmt, "Unknown Certificate provider: \"{}\"!", self.provider)
+    }
+}
+
+/// Settings for a certificate source which are not credentials. They can be set in the `settings`
+/// section of the config file or with `InputArgsBuilder::provider_settings`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ProviderSettings {
+    /// Maximum number of result pages requested from a paginated API. `None` fetches every page.
+    pub max_pages: Option<usize>,
+    /// Maximum number of results requested from an API which limits results instead of pages.
+    pub max_results: Option<usize>,
+    /// Base URL of the API of the source, ex. a caching mirror or a mock server. `None` uses the
+    /// public API.
+    pub base_url: Option<String>,
+    /// Retry policy for the requests sent to the API. `None` uses the default policy.
+    pub retry: Option<RetryPolicy>,
+}
+
+impl ProviderSettings {
+    /// Base URL to be used for the API, without trailing slashes.
+    pub fn base_url_or<'a>(&'a self, default: &'a str) -> &'a str {
+        self.base_url
+            .as_deref()
+            .unwrap_or(default)
+            .trim_end_matches('/')
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry.clone().unwrap_or_default()
+    }
+
+    /// Check that the limits allow fetching anything at all.
+    pub(crate) fn validate(&self) -> anyhow::Result<(), anyhow::Error> {
+        if self.max_pages == Some(0) {
+            return Err(anyhow!("\"max-pages\" must be greater than 0!"));
+        }
+        if self.max_results == Some(0) {
+            return Err(anyhow!("\"max-results\" must be greater than 0!"));
+        }
+        Ok(())
+    }
+
+    /// Fill in the settings missing from `self` with the ones from `other`.
+    pub(crate) fn or(self, other: &ProviderSettings) -> ProviderSettings {
+        ProviderSettings {
+            max_pages: self.max_pages.or(other.max_pages),
+            max_results: self.max_results.or(other.max_results),
+            base_url: self.base_url.or_else(|| other.base_url.clone()),
+            retry: self.retry.or_else(|| other.retry.clone()),
+        }
+    }
+}
+
+/// Everything a certificate source receives when asked to fetch certificates.
+#[derive(Debug, Clone, Copy)]
+pub struct FetchContext<'a> {
+    /// HTTP client shared by every source, configured with the proxy, TLS and timeout settings.
+    pub client: &'a reqwest::Client,
+    /// Section of the config file stored under the name of the source, if there is one.
+    pub config: Option<&'a serde_json::Value>,
+    pub settings: &'a ProviderSettings,
+}
+
+/// Certificate in which a domain name was found. Every field except the provider is optional, since
+/// not every provider returns every detail.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CertificateRecord {
+    /// Name of the certificate source which returned the certificate.
+    pub provider: String,
+    /// Identifier of the certificate at the provider, ex. the crt.sh ID.
+    pub id: Option<String>,
+    pub issuer: Option<String>,
+    pub serial_number: Option<String>,
+    pub not_before: Option<String>,
+    pub not_after: Option<String>,
+    /// SHA-256 fingerprint of the certificate.
+    pub fingerprint_sha256: Option<String>,
+    /// SHA-256 hash of the TBS (to be signed) part of the certificate.
+    pub tbs_sha256: Option<String>,
+    pub revoked: Option<bool>,
+}
+
+/// Names extracted from the certificates returned by a source.
+#[derive(Debug, Default, PartialEq)]
+pub struct FetchResult {
+    /// Every name found, together with the certificates it was found in.
+    pub names: HashMap<String, Vec<CertificateRecord>>,
+    /// Number of result pages retrieved from the API of the source.
+    pub pages: usize,
+    /// Errors which did not prevent the source from returning results, ex. a failing page.
+    pub errors: Vec<String>,
+}
+
+impl FetchResult {
+    pub fn new(pages: usize) -> FetchResult {
+        FetchResult {
+            pages,
+            ..FetchResult::default()
+        }
+    }
+
+    /// Record that `names` were found in `certificate`.
+    pub fn add_certificate<I: IntoIterator<Item = String>>(
+        &mut self,
+        names: I,
+        certificate: CertificateRecord,
+    ) {
+        for name in names.into_iter().collect::<HashSet<String>>() {
+            self.names
+                .entry(name)
+                .or_default()
+                .push(certificate.clone());
+        }
+    }
+
+    pub fn wildcards(&self) -> impl Iterator<Item = &String> {
+        self.names.keys().filter(|name| name.starts_with('*'))
+    }
+
+    pub fn fqdns(&self) -> impl Iterator<Item = &String> {
+        self.names.keys().filter(|name| !name.starts_with('*'))
+    }
+}
+
+/// A source of certificates from which domain names can be extracted.
+///
+/// Implementations are registered in a [`CertificateSourceRegistry`] and selected by their name
+/// (ex. `--provider=certsh`). The section of the config file stored under the same name is handed
+/// to the source for validation and fetching.
+#[async_trait]
+pub trait CertificateSource: Send + Sync + Debug {
+    /// Name used for selecting the source and for looking up its section in the config file.
+    fn name(&self) -> &str;
+
+    /// Example of the section expected in the config file. `None` if the source requires no
+    /// configuration.
+    fn config_schema(&self) -> Option<serde_json::Value>;
+
+    /// Validate the section of the config file belonging to this source. `config` is `None` if the
+    /// section is missing.
+    fn validate(&self, config: Option<&serde_json::Value>) -> anyhow::Result<(), anyhow::Error>;
+
+    /// Fetch the certificates issued for `domain` and return the names found in them.
+    async fn fetch(
+        &self,
+        domain: &str,
+        context: FetchContext<'_>,
+    ) -> anyhow::Result<FetchResult, anyhow::Error>;
+}
+
+/// Collection of certificate sources against which provider names are resolved.
+///
+/// `CertificateSourceRegistry::default()` contains the built-in sources: `certsh`, `censys` and
+/// `certspotter`.
+#[derive(Debug, Clone)]
+pub struct CertificateSourceRegistry {
+    sources: HashMap<String, Arc<dyn CertificateSource>>,
+}
+
+impl CertificateSourceRegistry {
+    /// Create a registry without any source.
+    pub fn empty() -> CertificateSourceRegistry {
+        CertificateSourceRegistry {
+            sources: HashMap::new(),
+        }
+    }
+
+    /// Register a source. A source already registered under the same name is replaced.
+    pub fn register(
+        &mut self,
+        source: Arc<dyn CertificateSource>,
+    ) -> &mut CertificateSourceRegistry {
+        self.sources.insert(source.name().to_string(), source);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn CertificateSource>> {
+        self.sources.get(name).cloned()
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        let mut names = self
+            .sources
+            .keys()
+            .map(|name| name.as_str())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    pub(crate) fn resolve(
+        &self,
+        name: &str,
+    ) -> anyhow::Result<Arc<dyn CertificateSource>, UnknownCertificateProvider> {
+        self.get(name)
+            .ok_or_else(|| UnknownCertificateProvider::new(name.to_string()))
+    }
+}
+
+impl Default for CertificateSourceRegistry {
+    fn default() -> CertificateSourceRegistry {
+        let mut registry = CertificateSourceRegistry::empty();
+        registry
+            .register(Arc::new(CrtShSource {}))
+            .register(Arc::new(CensysSource {}))
+            .register(Arc::new(CertSpotterSource {}));
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct StaticSource {}
+
+    #[async_trait]
+    impl CertificateSource for StaticSource {
+        fn name(&self) -> &str {
+            "static"
+        }
+
+        fn config_schema(&self) -> Option<serde_json::Value> {
+            None
+        }
+
+        fn validate(&self, _: Option<&serde_json::Value>) -> anyhow::Result<(), anyhow::Error> {
+            Ok(())
+        }
+
+        async fn fetch(
+            &self,
+            domain: &str,
+            _: FetchContext<'_>,
+        ) -> anyhow::Result<FetchResult, anyhow::Error> {
+            let mut result = FetchResult::new(1);
+            result.add_certificate(
+                [format!("*.{domain}"), format!("www.{domain}")],
+                CertificateRecord {
+                    provider: String::from("static"),
+                    ..CertificateRecord::default()
+                },
+            );
+            Ok(result)
+        }
+    }
+
+    #[test]
+    fn test_default_registry_contains_builtin_sources() {
+        let registry = CertificateSourceRegistry::default();
+        assert_eq!(vec!["censys", "certsh", "certspotter"], registry.names());
+        assert!(registry.resolve("unknown").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_register_custom_source() {
+        let mut registry = CertificateSourceRegistry::default();
+        registry.register(Arc::new(StaticSource {}));
+        let source = registry.resolve("static").expect("Source not registered");
+        let context = FetchContext {
+            client: &reqwest::Client::new(),
+            config: None,
+            settings: &ProviderSettings::default(),
+        };
+        let result = source.fetch("example.com", context).await.expect("Error");
+        assert_eq!(
+            vec!["*.example.com"],
+            result.wildcards().collect::<Vec<_>>()
+        );
+        assert_eq!(vec!["www.example.com"], result.fqdns().collect::<Vec<_>>());
+        assert_eq!(1, result.names["www.example.com"].len());
+    }
+}
@@ -1,6 +1,12 @@
+use crate::certificate_source::{
+    CertificateRecord, CertificateSource, FetchContext, FetchResult, ProviderSettings,
+};
+use crate::credentials::{mask, Credential, Credentials, ProviderHttpError};
+use crate::retry;
+use crate::retry::RetryPolicy;
 use anyhow::anyhow;
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
 use std::fmt::{Debug, Display};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -9,6 +15,12 @@ pub(crate) struct CertSpotterConfig {
     pub(crate) api_key: String,
 }
 
+impl Credential for CertSpotterConfig {
+    fn label(&self) -> String {
+        format!("api-key {}", mask(&self.api_key))
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 struct CertSpotterCertificate {
@@ -19,6 +31,27 @@ struct CertSpotterCertificate {
     not_before: String,
     not_after: String,
     revoked: bool,
+    issuer: Option<CertSpotterIssuer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CertSpotterIssuer {
+    name: Option<String>,
+}
+
+impl CertSpotterCertificate {
+    fn record(&self) -> CertificateRecord {
+        CertificateRecord {
+            provider: String::from("certspotter"),
+            id: Some(self.id.clone()),
+            issuer: self.issuer.as_ref().and_then(|issuer| issuer.name.clone()),
+            not_before: Some(self.not_before.clone()),
+            not_after: Some(self.not_after.clone()),
+            tbs_sha256: Some(self.tbs_sha256.clone()),
+            revoked: Some(self.revoked),
+            ..CertificateRecord::default()
+        }
+    }
 }
 
 #[allow(dead_code)]
@@ -28,53 +61,174 @@ struct CertSpotterError {
     message: String,
 }
 
-pub(crate) async fn fetch(
-    domain: String,
-    config: Vec<CertSpotterConfig>,
-) -> anyhow::Result<(Vec<String>, Vec<String>), anyhow::Error> {
-    let response = get_certificates(&domain, &config[0].api_key).await?;
+const CERTSPOTTER_URL: &str = "https://api.certspotter.com";
+
+#[derive(Debug)]
+pub(crate) struct CertSpotterSource {}
+
+impl CertSpotterSource {
+    fn parse_config(
+        config: Option<&serde_json::Value>,
+    ) -> anyhow::Result<Vec<CertSpotterConfig>, anyhow::Error> {
+        match config {
+            None => Err(anyhow!(
+                "CertSpotter requires secrets in the configuration file!"
+            )),
+            Some(value) => {
+                let secrets = serde_json::from_value::<Vec<CertSpotterConfig>>(value.clone())
+                    .map_err(|e| {
+                        anyhow!("Invalid CertSpotter secrets in the configuration file: {e}")
+                    })?;
+                if secrets.is_empty() {
+                    return Err(anyhow!("Empty array provided for CertSpotter secrets!"));
+                }
+                for (i, secret) in secrets.iter().enumerate() {
+                    if secret.api_key.trim().is_empty() {
+                        return Err(anyhow!(
+                            "CertSpotter secret #{} requires a non-empty \"api-key\"!",
+                            i + 1
+                        ));
+                    }
+                }
+                Ok(secrets)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl CertificateSource for CertSpotterSource {
+    fn name(&self) -> &str {
+        "certspotter"
+    }
 
-    let all_domains = response
-        .into_iter()
-        .flat_map(|response| response.dns_names)
-        .collect::<HashSet<String>>();
+    fn config_schema(&self) -> Option<serde_json::Value> {
+        Some(serde_json::json!([{"api-key": "<api-key>"}]))
+    }
 
-    let (wildcards, fqdns): (Vec<String>, Vec<String>) = all_domains
-        .into_iter()
-        .partition(|domain| domain.starts_with('*'));
+    fn validate(&self, config: Option<&serde_json::Value>) -> anyhow::Result<(), anyhow::Error> {
+        CertSpotterSource::parse_config(config).map(|_| ())
+    }
 
-    Ok((wildcards, fqdns))
+    async fn fetch(
+        &self,
+        domain: &str,
+        context: FetchContext<'_>,
+    ) -> anyhow::Result<FetchResult, anyhow::Error> {
+        fetch(
+            context.client,
+            domain,
+            CertSpotterSource::parse_config(context.config)?,
+            context.settings,
+        )
+        .await
+    }
 }
 
-async fn get_certificates<S>(
-    domain: S,
-    api_key: S,
-) -> anyhow::Result<Vec<CertSpotterCertificate>, anyhow::Error>
-where
-    S: AsRef<str> + Display,
-{
-    let client = reqwest::Client::new();
-    Ok(send_request(&client, domain, api_key).await?)
+async fn fetch(
+    client: &reqwest::Client,
+    domain: &str,
+    config: Vec<CertSpotterConfig>,
+    settings: &ProviderSettings,
+) -> anyhow::Result<FetchResult, anyhow::Error> {
+    let (response, pages, errors) = get_certificates(
+        client,
+        settings.base_url_or(CERTSPOTTER_URL),
+        domain,
+        &config,
+        settings,
+    )
+    .await?;
+
+    let mut result = FetchResult::new(pages);
+    for certificate in response {
+        let record = certificate.record();
+        result.add_certificate(certificate.dns_names, record);
+    }
+    result.errors = errors;
+    Ok(result)
+}
+
+// Walk the pages of issuances by following the `after` cursor, until an empty page is returned or
+// the page cap is reached. A failing first page fails the whole fetch, a failing later page stops
+// the pagination. Returns the certificates together with the number of pages fetched and the
+// errors of the failing page and the exhausted credentials.
+async fn get_certificates(
+    client: &reqwest::Client,
+    base_url: &str,
+    domain: &str,
+    config: &[CertSpotterConfig],
+    settings: &ProviderSettings,
+) -> anyhow::Result<(Vec<CertSpotterCertificate>, usize, Vec<String>), anyhow::Error> {
+    let retry_policy = settings.retry_policy();
+    let mut credentials = Credentials::new("CertSpotter", config);
+
+    let mut certificates = vec![];
+    let mut errors = vec![];
+    let mut after: Option<String> = None;
+    let mut pages = 0;
+    while settings.max_pages.is_none_or(|max| pages < max) {
+        let page = credentials
+            .send(|credential, last| {
+                // Wait for the rate limit to pass only if there is no other credential to try
+                send_request(
+                    client,
+                    base_url,
+                    domain,
+                    credential.api_key.as_str(),
+                    after.as_deref(),
+                    &retry_policy,
+                    last,
+                )
+            })
+            .await;
+        let page = match page {
+            Ok(page) => page,
+            Err(e) if pages == 0 => return Err(e),
+            Err(e) => {
+                errors.push(format!("Page {}: {}", pages + 1, e));
+                break;
+            }
+        };
+        pages += 1;
+        match page.last() {
+            Some(last) => after = Some(last.id.clone()),
+            None => break,
+        }
+        certificates.extend(page);
+    }
+
+    errors.splice(0..0, credentials.exhausted());
+    Ok((certificates, pages, errors))
 }
 
 async fn send_request<S>(
     client: &reqwest::Client,
+    base_url: S,
     domain: S,
     api_token: S,
+    after: Option<&str>,
+    retry_policy: &RetryPolicy,
+    wait_on_rate_limit: bool,
 ) -> anyhow::Result<Vec<CertSpotterCertificate>, anyhow::Error>
 where
     S: AsRef<str> + Display,
 {
-    let response = client
-        .get("https://api.certspotter.com/v1/issuances")
-        .query(&[
-            ("domain", domain.as_ref()),
-            ("include_subdomains", "true"),
-            ("expand", "dns_names"),
-        ])
-        .header("Authorization", format!("Bearer {}", api_token))
-        .send()
-        .await;
+    let mut query = vec![
+        ("domain", domain.as_ref()),
+        ("include_subdomains", "true"),
+        ("expand", "dns_names"),
+        ("expand", "issuer"),
+    ];
+    if let Some(after) = after {
+        query.push(("after", after));
+    }
+
+    let request = client
+        .get(format!("{}/v1/issuances", base_url))
+        .query(&query)
+        .header("Authorization", format!("Bearer {}", api_token));
+    let response = retry::send(retry_policy, request, wait_on_rate_limit).await;
 
     match response {
         Ok(response_content) => {
@@ -85,14 +239,184 @@ where
                     .map_err(anyhow::Error::from)
             } else {
                 let code = response_content.status();
-                let error_response = response_content.json::<CertSpotterError>().await?;
-                let error_massage = error_response.message;
-                Err(anyhow!(format!(
-                    "CertSpotter responded with HTTP code \"{code}\" and with message of: \"{error_massage}\"\n\
-                    You may want to try other provider!"
-                )))
+                let error_massage = response_content
+                    .json::<CertSpotterError>()
+                    .await
+                    .map(|error_response| error_response.message)
+                    .unwrap_or_default();
+                Err(anyhow::Error::from(ProviderHttpError {
+                    status: code,
+                    message: format!(
+                        "CertSpotter responded with HTTP code \"{code}\" and with message of: \"{error_massage}\"\n\
+                        You may want to try other provider!"
+                    ),
+                }))
             }
         }
         Err(err_content) => Err(anyhow!(err_content)),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path, query_param, query_param_is_missing};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn issuance(id: &str, dns_name: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": id,
+            "tbs_sha256": "tbs",
+            "dns_names": [dns_name],
+            "pubkey_sha256": "pubkey",
+            "not_before": "2024-01-01T00:00:00Z",
+            "not_after": "2025-01-01T00:00:00Z",
+            "revoked": false
+        })
+    }
+
+    fn credentials() -> Vec<CertSpotterConfig> {
+        vec![
+            CertSpotterConfig {
+                api_key: String::from("first-key"),
+            },
+            CertSpotterConfig {
+                api_key: String::from("second-key"),
+            },
+        ]
+    }
+
+    async fn mock_pages(server: &MockServer) {
+        Mock::given(method("GET"))
+            .and(path("/v1/issuances"))
+            .and(query_param_is_missing("after"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                issuance("1", "a.example.com"),
+                issuance("2", "*.example.com")
+            ])))
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/issuances"))
+            .and(query_param("after", "2"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!([issuance("3", "b.example.com")])),
+            )
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/issuances"))
+            .and(query_param("after", "3"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([])))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_get_certificates_follows_cursor() {
+        let server = MockServer::start().await;
+        mock_pages(&server).await;
+
+        let (certificates, pages, _) = get_certificates(
+            &reqwest::Client::new(),
+            server.uri().as_str(),
+            "example.com",
+            &credentials(),
+            &ProviderSettings::default(),
+        )
+        .await
+        .expect("Error");
+        assert_eq!(
+            vec!["1", "2", "3"],
+            certificates
+                .iter()
+                .map(|certificate| certificate.id.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(3, pages);
+    }
+
+    #[tokio::test]
+    async fn test_get_certificates_page_cap() {
+        let server = MockServer::start().await;
+        mock_pages(&server).await;
+
+        let settings = ProviderSettings {
+            max_pages: Some(1),
+            ..ProviderSettings::default()
+        };
+        let (certificates, pages, _) = get_certificates(
+            &reqwest::Client::new(),
+            server.uri().as_str(),
+            "example.com",
+            &credentials(),
+            &settings,
+        )
+        .await
+        .expect("Error");
+        assert_eq!(2, certificates.len());
+        assert_eq!(1, pages);
+    }
+
+    #[tokio::test]
+    async fn test_get_certificates_retries_rate_limited_page() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/issuances"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        mock_pages(&server).await;
+
+        let config = [CertSpotterConfig {
+            api_key: String::from("key"),
+        }];
+        let (certificates, _, exhausted) = get_certificates(
+            &reqwest::Client::new(),
+            server.uri().as_str(),
+            "example.com",
+            &config,
+            &ProviderSettings::default(),
+        )
+        .await
+        .expect("Error");
+        assert_eq!(3, certificates.len());
+        assert!(exhausted.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_certificates_fails_over_to_next_credential() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/issuances"))
+            .and(header("Authorization", "Bearer first-key"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(serde_json::json!({
+                "code": "unauthorized",
+                "message": "Invalid API key"
+            })))
+            .mount(&server)
+            .await;
+        mock_pages(&server).await;
+
+        let (certificates, _, exhausted) = get_certificates(
+            &reqwest::Client::new(),
+            server.uri().as_str(),
+            "example.com",
+            &credentials(),
+            &ProviderSettings::default(),
+        )
+        .await
+        .expect("Error");
+        assert_eq!(3, certificates.len());
+        assert_eq!(1, exhausted.len());
+        assert!(exhausted[0].contains("credential #1"));
+    }
+
+    #[test]
+    fn test_parse_config_rejects_empty_entry() {
+        let config = serde_json::json!([{"api-key": "key"}, {"api-key": " "}]);
+        assert!(CertSpotterSource::parse_config(Some(&config)).is_err());
+    }
+}
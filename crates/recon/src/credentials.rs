@@ -0,0 +1,170 @@
+use anyhow::anyhow;
+use reqwest::StatusCode;
+use std::fmt::{Debug, Display};
+use std::future::Future;
+
+/// Error response from a provider API. Kept typed, so that the failing HTTP status can be inspected
+/// after the error was converted into an `anyhow::Error`.
+#[derive(Debug)]
+pub(crate) struct ProviderHttpError {
+    pub(crate) status: StatusCode,
+    pub(crate) message: String,
+}
+
+impl Display for ProviderHttpError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> anyhow::Result<(), std::fmt::Error> {
+        write!(fmt, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ProviderHttpError {}
+
+/// Credential from the config file of a provider.
+pub(crate) trait Credential {
+    /// Identifier of the credential which is safe to display, ex. a prefix of the API key.
+    fn label(&self) -> String;
+}
+
+/// Returns the first characters of `secret`, used for telling credentials apart in reports.
+pub(crate) fn mask(secret: &str) -> String {
+    format!("{}...", secret.chars().take(6).collect::<String>())
+}
+
+// Unauthorized, forbidden and rate limited responses mean that the credential is unusable (for now)
+fn is_exhausted(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<ProviderHttpError>()
+        .is_some_and(|error| {
+            matches!(
+                error.status,
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS
+            )
+        })
+}
+
+/// Credentials of a provider used one after the other: when the current credential is exhausted
+/// the request is retried with the next one, which is then used for every following request.
+pub(crate) struct Credentials<'a, C: Credential> {
+    provider: &'a str,
+    credentials: &'a [C],
+    current: usize,
+    exhausted: Vec<String>,
+}
+
+impl<'a, C: Credential> Credentials<'a, C> {
+    pub(crate) fn new(provider: &'a str, credentials: &'a [C]) -> Credentials<'a, C> {
+        Credentials {
+            provider,
+            credentials,
+            current: 0,
+            exhausted: vec![],
+        }
+    }
+
+    /// Send a request with the current credential, failing over to the next credential as long as
+    /// the request is rejected because of the credential. `request` is told whether the credential
+    /// it receives is the last one available.
+    pub(crate) async fn send<T, F, Fut>(
+        &mut self,
+        mut request: F,
+    ) -> anyhow::Result<T, anyhow::Error>
+    where
+        F: FnMut(&'a C, bool) -> Fut,
+        Fut: Future<Output = anyhow::Result<T, anyhow::Error>>,
+    {
+        loop {
+            let credential = match self.credentials.get(self.current) {
+                Some(credential) => credential,
+                None => {
+                    return Err(anyhow!(
+                        "All {} credentials are exhausted! {}",
+                        self.provider,
+                        self.exhausted.join("; ")
+                    ))
+                }
+            };
+            let last = self.current + 1 == self.credentials.len();
+            match request(credential, last).await {
+                Err(e) if is_exhausted(&e) => {
+                    self.exhausted.push(format!(
+                        "{} credential #{} ({}) exhausted: {}",
+                        self.provider,
+                        self.current + 1,
+                        credential.label(),
+                        e
+                    ));
+                    self.current += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Reports about the credentials exhausted so far.
+    pub(crate) fn exhausted(&self) -> Vec<String> {
+        self.exhausted.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Key(&'static str);
+
+    impl Credential for Key {
+        fn label(&self) -> String {
+            mask(self.0)
+        }
+    }
+
+    fn rejected(status: StatusCode) -> anyhow::Error {
+        anyhow::Error::from(ProviderHttpError {
+            status,
+            message: status.to_string(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_send_fails_over_to_next_credential() {
+        let keys = [Key("first-key"), Key("second-key")];
+        let mut credentials = Credentials::new("Test", &keys);
+
+        let used = credentials
+            .send(|key, _| async move {
+                match key.0 {
+                    "first-key" => Err(rejected(StatusCode::TOO_MANY_REQUESTS)),
+                    other => Ok(other),
+                }
+            })
+            .await
+            .expect("Error");
+        assert_eq!("second-key", used);
+        assert_eq!(1, credentials.exhausted().len());
+        assert!(credentials.exhausted()[0].contains("first-..."));
+    }
+
+    #[tokio::test]
+    async fn test_send_returns_other_errors() {
+        let keys = [Key("first-key"), Key("second-key")];
+        let mut credentials = Credentials::new("Test", &keys);
+
+        let result: anyhow::Result<()> = credentials
+            .send(|_, _| async { Err(rejected(StatusCode::INTERNAL_SERVER_ERROR)) })
+            .await;
+        assert!(result.is_err());
+        assert!(credentials.exhausted().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_send_all_credentials_exhausted() {
+        let keys = [Key("first-key"), Key("second-key")];
+        let mut credentials = Credentials::new("Test", &keys);
+
+        let result: anyhow::Result<()> = credentials
+            .send(|_, _| async { Err(rejected(StatusCode::UNAUTHORIZED)) })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(2, credentials.exhausted().len());
+    }
+}
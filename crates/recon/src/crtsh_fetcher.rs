@@ -1,6 +1,9 @@
+use crate::certificate_source::{CertificateRecord, CertificateSource, FetchContext, FetchResult};
+use crate::retry;
+use crate::retry::RetryPolicy;
 use anyhow::anyhow;
+use async_trait::async_trait;
 use serde::Deserialize;
-use std::collections::HashSet;
 use std::fmt::{Debug, Display};
 
 #[allow(dead_code)]
@@ -17,45 +20,90 @@ struct Certificate {
     serial_number: String,
 }
 
-pub(crate) async fn fetch<S>(domain: S) -> anyhow::Result<(Vec<String>, Vec<String>), anyhow::Error>
+const CRTSH_URL: &str = "https://crt.sh";
+
+#[derive(Debug)]
+pub(crate) struct CrtShSource {}
+
+#[async_trait]
+impl CertificateSource for CrtShSource {
+    fn name(&self) -> &str {
+        "certsh"
+    }
+
+    fn config_schema(&self) -> Option<serde_json::Value> {
+        // No config required for CrtSh
+        None
+    }
+
+    fn validate(&self, _: Option<&serde_json::Value>) -> anyhow::Result<(), anyhow::Error> {
+        Ok(())
+    }
+
+    async fn fetch(
+        &self,
+        domain: &str,
+        context: FetchContext<'_>,
+    ) -> anyhow::Result<FetchResult, anyhow::Error> {
+        fetch(
+            context.client,
+            context.settings.base_url_or(CRTSH_URL),
+            domain,
+            &context.settings.retry_policy(),
+        )
+        .await
+    }
+}
+
+async fn fetch<S>(
+    client: &reqwest::Client,
+    base_url: S,
+    domain: S,
+    retry_policy: &RetryPolicy,
+) -> anyhow::Result<FetchResult, anyhow::Error>
 where
     S: AsRef<str> + Display,
 {
-    let certificates = get_certificates(&domain).await?;
+    let certificates = get_certificates(client, &base_url, &domain, retry_policy).await?;
 
-    let mut domains: HashSet<String> = HashSet::new();
+    // crt.sh returns every certificate in a single response
+    let mut result = FetchResult::new(1);
     for certificate in certificates {
-        domains.extend(
-            certificate
-                .name_value
-                .split('\n')
-                .map(|s| s.to_string())
-                .collect::<HashSet<String>>(),
-        );
-        domains.insert(certificate.common_name);
+        let record = CertificateRecord {
+            provider: String::from("certsh"),
+            id: Some(certificate.id.to_string()),
+            issuer: Some(certificate.issuer_name),
+            serial_number: Some(certificate.serial_number),
+            not_before: Some(certificate.not_before),
+            not_after: Some(certificate.not_after),
+            ..CertificateRecord::default()
+        };
+        let names = certificate
+            .name_value
+            .split('\n')
+            .map(|s| s.to_string())
+            .chain(std::iter::once(certificate.common_name));
+        result.add_certificate(names, record);
     }
 
-    let (wildcards, fqdns): (Vec<String>, Vec<String>) = domains
-        .into_iter()
-        .partition(|domain| domain.starts_with('*'));
-
-    Ok((wildcards, fqdns))
+    Ok(result)
 }
 
-async fn get_certificates<S>(domain: S) -> anyhow::Result<Vec<Certificate>, anyhow::Error>
+async fn get_certificates<S>(
+    client: &reqwest::Client,
+    base_url: S,
+    domain: S,
+    retry_policy: &RetryPolicy,
+) -> anyhow::Result<Vec<Certificate>, anyhow::Error>
 where
     S: AsRef<str> + Display,
 {
-    let client = reqwest::Client::new();
-    let response = client
-        .get("https://crt.sh")
-        .query(&[
-            ("q", domain.as_ref()),
-            ("output", "json"),
-            ("excluded", "expired"),
-        ])
-        .send()
-        .await;
+    let request = client.get(base_url.as_ref()).query(&[
+        ("q", domain.as_ref()),
+        ("output", "json"),
+        ("excluded", "expired"),
+    ]);
+    let response = retry::send(retry_policy, request, true).await;
     match response {
         Ok(response_content) => {
             if response_content.status().is_success() {
@@ -74,3 +122,60 @@ where
         Err(err_content) => Err(anyhow!(err_content)),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::certificate_source::ProviderSettings;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_fetch_from_base_url() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/"))
+            .and(query_param("q", "example.com"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!([{
+                    "issuer_ca_id": 1,
+                    "issuer_name": "C=US, O=Let's Encrypt, CN=R3",
+                    "common_name": "example.com",
+                    "name_value": "example.com\n*.example.com",
+                    "id": 1,
+                    "entry_timestamp": "2024-01-01T00:00:00",
+                    "not_before": "2024-01-01T00:00:00",
+                    "not_after": "2025-01-01T00:00:00",
+                    "serial_number": "01"
+                }])),
+            )
+            .mount(&server)
+            .await;
+
+        let settings = ProviderSettings {
+            base_url: Some(format!("{}/", server.uri())),
+            ..ProviderSettings::default()
+        };
+        let context = FetchContext {
+            client: &reqwest::Client::new(),
+            config: None,
+            settings: &settings,
+        };
+        let result = CrtShSource {}
+            .fetch("example.com", context)
+            .await
+            .expect("Error");
+        assert_eq!(
+            vec!["*.example.com"],
+            result.wildcards().collect::<Vec<_>>()
+        );
+        assert_eq!(vec!["example.com"], result.fqdns().collect::<Vec<_>>());
+        let record = &result.names["example.com"][0];
+        assert_eq!("certsh", record.provider);
+        assert_eq!(Some(String::from("01")), record.serial_number);
+        assert_eq!(
+            Some(String::from("C=US, O=Let's Encrypt, CN=R3")),
+            record.issuer
+        );
+    }
+}
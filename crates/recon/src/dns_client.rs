@@ -0,0 +1,532 @@
+use async_std_resolver::lookup::Lookup;
+use async_std_resolver::lookup_ip::LookupIp;
+use async_std_resolver::proto::op::{Message, MessageType, OpCode, Query, ResponseCode};
+use async_std_resolver::proto::rr::{Name, RecordType};
+use async_std_resolver::{AsyncStdResolver, ResolveError};
+use hickory_resolver::error::ResolveErrorKind;
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tokio_native_tls::{TlsConnector, TlsStream};
+
+type TlsConnection = TlsStream<TcpStream>;
+
+const DNS_MESSAGE: &str = "application/dns-message";
+
+// Timeout of a DNS over TLS exchange, if none is configured for the HTTP client
+const DEFAULT_TLS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Resolver reached through an encrypted connection.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum EncryptedEndpoint {
+    /// DNS over HTTPS (RFC 8484), sent through the HTTP client of the crate.
+    Https(String),
+    /// DNS over TLS (RFC 7858).
+    Tls { host: String, port: u16 },
+}
+
+impl Display for EncryptedEndpoint {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> anyhow::Result<(), std::fmt::Error> {
+        match self {
+            EncryptedEndpoint::Https(url) => write!(fmt, "{}", url),
+            EncryptedEndpoint::Tls { host, port } => write!(fmt, "tls://{}:{}", host, port),
+        }
+    }
+}
+
+/// Resolves names through the encrypted endpoints, trying them one after the other, and falls
+/// back to the plain resolver if none of them answers. Failing endpoints, including the ones
+/// answering with an error such as SERVFAIL or REFUSED, are skipped for the next one.
+pub(crate) struct DnsClient {
+    plain: Option<AsyncStdResolver>,
+    encrypted: Vec<EncryptedEndpoint>,
+    http_client: reqwest::Client,
+    tls_connector: TlsConnector,
+    tls_timeout: Duration,
+    // Idle DNS over TLS connections by host and port, reused for the next queries as recommended
+    // by RFC 7858. There are never more of them than queries sent at the same time.
+    tls_connections: Mutex<HashMap<(String, u16), Vec<TlsConnection>>>,
+    // Lookups in flight, shared by all the targets scanned at the same time
+    permits: Semaphore,
+}
+
+impl DnsClient {
+    pub(crate) fn new(
+        plain: Option<AsyncStdResolver>,
+        encrypted: Vec<EncryptedEndpoint>,
+        http_client: reqwest::Client,
+        tls_connector: TlsConnector,
+        tls_timeout: Option<Duration>,
+        max_parallel_requests: usize,
+    ) -> DnsClient {
+        DnsClient {
+            plain,
+            encrypted,
+            http_client,
+            tls_connector,
+            tls_timeout: tls_timeout.unwrap_or(DEFAULT_TLS_TIMEOUT),
+            tls_connections: Mutex::new(HashMap::new()),
+            permits: Semaphore::new(max_parallel_requests.max(1)),
+        }
+    }
+
+    /// Look up the IPv4 addresses of `name`, or the IPv6 addresses if it has no IPv4 address.
+    pub(crate) async fn lookup_ip(&self, name: &str) -> anyhow::Result<LookupIp, ResolveError> {
+        let _permit = self.acquire().await?;
+        if self.encrypted.is_empty() {
+            return self.plain_resolver()?.lookup_ip(name).await;
+        }
+        match self.query(name, RecordType::A).await {
+            Err(e) if crate::is_missing_record(&e) => {
+                self.query(name, RecordType::AAAA).await.map(LookupIp::from)
+            }
+            result => result.map(LookupIp::from),
+        }
+    }
+
+    pub(crate) async fn lookup(
+        &self,
+        name: &str,
+        record_type: RecordType,
+    ) -> anyhow::Result<Lookup, ResolveError> {
+        let _permit = self.acquire().await?;
+        self.query(name, record_type).await
+    }
+
+    // The semaphore is never closed
+    async fn acquire(&self) -> anyhow::Result<SemaphorePermit<'_>, ResolveError> {
+        self.permits
+            .acquire()
+            .await
+            .map_err(|e| ResolveError::from(e.to_string()))
+    }
+
+    async fn query(
+        &self,
+        name: &str,
+        record_type: RecordType,
+    ) -> anyhow::Result<Lookup, ResolveError> {
+        if self.encrypted.is_empty() {
+            return self.plain_resolver()?.lookup(name, record_type).await;
+        }
+
+        let query = Query::query(Name::from_utf8(name)?, record_type);
+        let mut last_error = None;
+        for endpoint in &self.encrypted {
+            match self.exchange(endpoint, &query).await {
+                Ok(response) => match to_lookup(query.clone(), response) {
+                    // Only a missing record is an answer, the next endpoint may not fail
+                    Err(e) if !crate::is_missing_record(&e) => last_error = Some(e),
+                    result => return result,
+                },
+                Err(e) => last_error = Some(exchange_error(endpoint, e)),
+            }
+        }
+
+        match (&self.plain, last_error) {
+            (Some(plain), _) => plain.lookup(name, record_type).await,
+            (None, Some(e)) => Err(e),
+            (None, None) => Err(ResolveError::from("No DNS resolver configured!")),
+        }
+    }
+
+    fn plain_resolver(&self) -> anyhow::Result<&AsyncStdResolver, ResolveError> {
+        self.plain
+            .as_ref()
+            .ok_or_else(|| ResolveError::from("No DNS resolver configured!"))
+    }
+
+    async fn exchange(
+        &self,
+        endpoint: &EncryptedEndpoint,
+        query: &Query,
+    ) -> anyhow::Result<Message, anyhow::Error> {
+        match endpoint {
+            // The ID is always 0 for DNS over HTTPS, which makes the responses cacheable
+            EncryptedEndpoint::Https(url) => {
+                let request = request_message(0, query).to_vec()?;
+                let response = self
+                    .http_client
+                    .post(url)
+                    .header(CONTENT_TYPE, DNS_MESSAGE)
+                    .header(ACCEPT, DNS_MESSAGE)
+                    .body(request)
+                    .send()
+                    .await?
+                    .error_for_status()?;
+                Ok(Message::from_vec(&response.bytes().await?)?)
+            }
+            EncryptedEndpoint::Tls { host, port } => {
+                let id = rand::random::<u16>();
+                let request = request_message(id, query).to_vec()?;
+                let response = tokio::time::timeout(
+                    self.tls_timeout,
+                    self.exchange_tls(host, *port, &request),
+                )
+                .await??;
+                let response = Message::from_vec(&response)?;
+                if response.id() != id {
+                    return Err(anyhow::anyhow!("Response to another query"));
+                }
+                Ok(response)
+            }
+        }
+    }
+
+    // An idle connection is used if there is one. The server may have closed it in the meantime,
+    // the query is sent again through a new connection in that case.
+    async fn exchange_tls(
+        &self,
+        host: &str,
+        port: u16,
+        request: &[u8],
+    ) -> anyhow::Result<Vec<u8>, anyhow::Error> {
+        let key = (host.to_string(), port);
+        let idle = self
+            .tls_connections
+            .lock()
+            .ok()
+            .and_then(|mut connections| connections.get_mut(&key)?.pop());
+        let (mut stream, response) = match idle {
+            Some(mut stream) => match send_tls(&mut stream, request).await {
+                Ok(response) => (stream, response),
+                Err(_) => self.exchange_new_tls(host, port, request).await?,
+            },
+            None => self.exchange_new_tls(host, port, request).await?,
+        };
+        if let Ok(mut connections) = self.tls_connections.lock() {
+            connections.entry(key).or_default().push(stream);
+        } else {
+            let _ = stream.shutdown().await;
+        }
+        Ok(response)
+    }
+
+    async fn exchange_new_tls(
+        &self,
+        host: &str,
+        port: u16,
+        request: &[u8],
+    ) -> anyhow::Result<(TlsConnection, Vec<u8>), anyhow::Error> {
+        let stream = TcpStream::connect((host, port)).await?;
+        let mut stream = self.tls_connector.connect(host, stream).await?;
+        let response = send_tls(&mut stream, request).await?;
+        Ok((stream, response))
+    }
+}
+
+// Messages are prefixed with their length on the TLS stream, just like on TCP
+async fn send_tls(
+    stream: &mut TlsConnection,
+    request: &[u8],
+) -> anyhow::Result<Vec<u8>, anyhow::Error> {
+    let length = u16::try_from(request.len())?;
+    stream.write_all(&length.to_be_bytes()).await?;
+    stream.write_all(request).await?;
+    stream.flush().await?;
+
+    let mut length = [0u8; 2];
+    stream.read_exact(&mut length).await?;
+    let mut response = vec![0u8; usize::from(u16::from_be_bytes(length))];
+    stream.read_exact(&mut response).await?;
+    Ok(response)
+}
+
+fn request_message(id: u16, query: &Query) -> Message {
+    let mut message = Message::new();
+    message
+        .set_id(id)
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .set_recursion_desired(true)
+        .add_query(query.clone());
+    message
+}
+
+// Timeouts are kept apart from the other failures, so that they can be reported as such
+fn exchange_error(endpoint: &EncryptedEndpoint, error: anyhow::Error) -> ResolveError {
+    let timed_out = error.is::<tokio::time::error::Elapsed>()
+        || error
+            .downcast_ref::<reqwest::Error>()
+            .is_some_and(|e| e.is_timeout());
+    if timed_out {
+        return ResolveError::from(ResolveErrorKind::Timeout);
+    }
+    ResolveError::from(format!("DNS resolver {} failed: {}", endpoint, error))
+}
+
+// Answers without a record of the queried type mean that the name has no such record, just like
+// NXDOMAIN responses. Error responses are reported with their response code, the same way as the
+// resolver of hickory does.
+fn to_lookup(query: Query, mut response: Message) -> anyhow::Result<Lookup, ResolveError> {
+    let response_code = response.response_code();
+    let trusted = matches!(
+        response_code,
+        ResponseCode::NoError | ResponseCode::NXDomain
+    );
+    let answers = response.take_answers();
+    if !trusted
+        || !answers
+            .iter()
+            .any(|record| record.record_type() == query.query_type())
+    {
+        return Err(ResolveError::from(ResolveErrorKind::NoRecordsFound {
+            query: Box::new(query),
+            soa: None,
+            negative_ttl: None,
+            response_code,
+            trusted,
+        }));
+    }
+    Ok(Lookup::new_with_max_ttl(query, Arc::from(answers)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LookupStatus;
+    use async_std_resolver::proto::rr::rdata::A;
+    use async_std_resolver::proto::rr::{RData, Record};
+    use std::str::FromStr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::net::TcpListener;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn response(response_code: ResponseCode, answers: Vec<Record>) -> Vec<u8> {
+        let query = Query::query(
+            Name::from_str("www.example.com.").expect("Error"),
+            RecordType::A,
+        );
+        let mut message = request_message(0, &query);
+        message
+            .set_message_type(MessageType::Response)
+            .set_response_code(response_code)
+            .add_answers(answers);
+        message.to_vec().expect("Error")
+    }
+
+    async fn doh_client(server: &MockServer) -> DnsClient {
+        DnsClient::new(
+            None,
+            vec![EncryptedEndpoint::Https(format!(
+                "{}/dns-query",
+                server.uri()
+            ))],
+            reqwest::Client::new(),
+            TlsConnector::from(native_tls::TlsConnector::new().expect("Error")),
+            None,
+            10,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_lookup_ip_over_https() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/dns-query"))
+            .and(header("content-type", DNS_MESSAGE))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                response(
+                    ResponseCode::NoError,
+                    vec![Record::from_rdata(
+                        Name::from_str("www.example.com.").expect("Error"),
+                        300,
+                        RData::A(A::new(192, 0, 2, 1)),
+                    )],
+                ),
+                DNS_MESSAGE,
+            ))
+            .mount(&server)
+            .await;
+
+        let lookup = doh_client(&server)
+            .await
+            .lookup_ip("www.example.com")
+            .await
+            .expect("Error");
+        assert_eq!(
+            vec!["192.0.2.1"],
+            lookup.iter().map(|ip| ip.to_string()).collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lookup_over_https_missing_record() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_raw(response(ResponseCode::NXDomain, vec![]), DNS_MESSAGE),
+            )
+            .mount(&server)
+            .await;
+
+        let error = doh_client(&server)
+            .await
+            .lookup_ip("www.example.com")
+            .await
+            .expect_err("Error");
+        assert!(crate::is_missing_record(&error));
+    }
+
+    #[tokio::test]
+    async fn test_lookup_over_https_server_failure() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_raw(response(ResponseCode::ServFail, vec![]), DNS_MESSAGE),
+            )
+            .mount(&server)
+            .await;
+
+        let error = doh_client(&server)
+            .await
+            .lookup_ip("www.example.com")
+            .await
+            .expect_err("Error");
+        assert_eq!(LookupStatus::ServFail, LookupStatus::classify(&error));
+    }
+
+    #[tokio::test]
+    async fn test_lookup_over_https_failing_endpoint() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        let error = doh_client(&server)
+            .await
+            .lookup("www.example.com", RecordType::MX)
+            .await
+            .expect_err("Error");
+        assert!(!crate::is_missing_record(&error));
+    }
+
+    #[tokio::test]
+    async fn test_lookup_over_https_next_endpoint() {
+        let failing = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_raw(response(ResponseCode::Refused, vec![]), DNS_MESSAGE),
+            )
+            .expect(1)
+            .mount(&failing)
+            .await;
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_raw(response(ResponseCode::NXDomain, vec![]), DNS_MESSAGE),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = DnsClient::new(
+            None,
+            vec![
+                EncryptedEndpoint::Https(format!("{}/dns-query", failing.uri())),
+                EncryptedEndpoint::Https(format!("{}/dns-query", server.uri())),
+            ],
+            reqwest::Client::new(),
+            TlsConnector::from(native_tls::TlsConnector::new().expect("Error")),
+            None,
+            10,
+        );
+        let error = client
+            .lookup("www.example.com", RecordType::A)
+            .await
+            .expect_err("Error");
+        assert!(crate::is_missing_record(&error));
+    }
+
+    // Answers every query of a connection with the same address and counts the connections
+    async fn dot_server(connections: Arc<AtomicUsize>) -> u16 {
+        let identity = native_tls::Identity::from_pkcs8(
+            include_bytes!("../testdata/localhost.pem"),
+            include_bytes!("../testdata/localhost.key"),
+        )
+        .expect("Error");
+        let acceptor = tokio_native_tls::TlsAcceptor::from(
+            native_tls::TlsAcceptor::new(identity).expect("Error"),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("Error");
+        let port = listener.local_addr().expect("Error").port();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                connections.fetch_add(1, Ordering::SeqCst);
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    let mut stream = acceptor.accept(stream).await.expect("Error");
+                    let mut length = [0u8; 2];
+                    while stream.read_exact(&mut length).await.is_ok() {
+                        let mut request = vec![0u8; usize::from(u16::from_be_bytes(length))];
+                        stream.read_exact(&mut request).await.expect("Error");
+                        let request = Message::from_vec(&request).expect("Error");
+                        let mut message = Message::from_vec(&response(
+                            ResponseCode::NoError,
+                            vec![Record::from_rdata(
+                                Name::from_str("www.example.com.").expect("Error"),
+                                300,
+                                RData::A(A::new(192, 0, 2, 1)),
+                            )],
+                        ))
+                        .expect("Error");
+                        message.set_id(request.id());
+                        let message = message.to_vec().expect("Error");
+                        let length = u16::try_from(message.len()).expect("Error");
+                        stream
+                            .write_all(&length.to_be_bytes())
+                            .await
+                            .expect("Error");
+                        stream.write_all(&message).await.expect("Error");
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn test_lookup_ip_over_tls() {
+        let connections = Arc::new(AtomicUsize::new(0));
+        let port = dot_server(connections.clone()).await;
+        let certificate =
+            native_tls::Certificate::from_pem(include_bytes!("../testdata/localhost.pem"))
+                .expect("Error");
+        let client = DnsClient::new(
+            None,
+            vec![EncryptedEndpoint::Tls {
+                host: String::from("localhost"),
+                port,
+            }],
+            reqwest::Client::new(),
+            TlsConnector::from(
+                native_tls::TlsConnector::builder()
+                    .add_root_certificate(certificate)
+                    .build()
+                    .expect("Error"),
+            ),
+            None,
+            10,
+        );
+
+        for _ in 0..2 {
+            let lookup = client.lookup_ip("www.example.com").await.expect("Error");
+            assert_eq!(
+                vec!["192.0.2.1"],
+                lookup.iter().map(|ip| ip.to_string()).collect::<Vec<_>>()
+            );
+        }
+        assert_eq!(1, connections.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,181 @@
+use crate::report::{ProviderSummary, ReconError, ReconReport, Stage};
+use crate::scope::OutOfScope;
+use crate::DomainInfo;
+use futures::channel::mpsc::UnboundedSender;
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime};
+
+/// Progress of a reconnaissance run, emitted by `run_stream` as it happens.
+#[derive(Debug)]
+pub enum ReconEvent {
+    /// A stage of the scan of `target` started. `step` counts from 1 up to `steps`, the number of
+    /// stages of the scan.
+    StageStarted {
+        target: String,
+        stage: Stage,
+        step: usize,
+        steps: usize,
+    },
+    /// A certificate provider returned its results.
+    ProviderFinished(ProviderSummary),
+    /// Names found in the certificates of a target were left out by the scope.
+    OutOfScope(OutOfScope),
+    /// The certificate providers found no names for the target, its scan finishes without
+    /// resolving anything.
+    NoDomainsFound(String),
+    /// A domain was resolved.
+    DomainResolved(DomainInfo),
+    /// A domain found in the certificates could not be resolved, its status tells why. Emitted
+    /// only if unresolved domains are included.
+    DomainUnresolved(DomainInfo),
+    /// Failure which left out part of the results, ex. a failing certificate provider.
+    Error(ReconError),
+    /// Failure which is unlikely to affect the results, ex. a timed out DNS lookup.
+    Warning(ReconError),
+    /// Last event of a successful run, with the report of the whole run. The domains are not
+    /// kept in the report, they were already emitted with `DomainResolved` and
+    /// `DomainUnresolved`.
+    Finished(ReconReport),
+    /// Last event of a run which could not continue.
+    Failed(ReconError),
+}
+
+/// Emits the events of a run and collects them into the report of the run at the same time. The
+/// targets of the run are scanned concurrently, each of them emitting through the same `Events`.
+pub(crate) struct Events {
+    sender: Option<UnboundedSender<ReconEvent>>,
+    report: Mutex<ReconReport>,
+    // Whether the resolved domains are collected into the report, which grows with every domain
+    keep_domains: bool,
+    started: Instant,
+}
+
+impl Events {
+    /// Without a sender the events are only collected into the report, including the domains.
+    /// With a sender the domains are only emitted.
+    pub(crate) fn new(sender: Option<UnboundedSender<ReconEvent>>, targets: &[String]) -> Events {
+        Events {
+            keep_domains: sender.is_none(),
+            sender,
+            report: Mutex::new(ReconReport {
+                targets: targets.to_vec(),
+                started_at: Some(SystemTime::now()),
+                ..ReconReport::default()
+            }),
+            started: Instant::now(),
+        }
+    }
+
+    // The lock is never held across an await, so it can not be poisoned by another target
+    fn update(&self, update: impl FnOnce(&mut ReconReport)) {
+        if let Ok(mut report) = self.report.lock() {
+            update(&mut report);
+        }
+    }
+
+    fn send(&self, event: ReconEvent) {
+        if let Some(sender) = &self.sender {
+            // The receiver may have been dropped, in that case nobody is interested in the events
+            let _ = sender.unbounded_send(event);
+        }
+    }
+
+    pub(crate) fn stage_started(&self, target: &str, stage: Stage, step: usize, steps: usize) {
+        self.send(ReconEvent::StageStarted {
+            target: target.to_string(),
+            stage,
+            step,
+            steps,
+        });
+    }
+
+    pub(crate) fn provider_finished(&self, summary: ProviderSummary) {
+        self.update(|report| report.providers.push(summary.clone()));
+        self.send(ReconEvent::ProviderFinished(summary));
+    }
+
+    pub(crate) fn out_of_scope(&self, out_of_scope: OutOfScope) {
+        self.update(|report| report.out_of_scope.push(out_of_scope.clone()));
+        self.send(ReconEvent::OutOfScope(out_of_scope));
+    }
+
+    pub(crate) fn no_domains_found(&self, target: &str) {
+        self.send(ReconEvent::NoDomainsFound(target.to_string()));
+    }
+
+    pub(crate) fn domain_resolved(&self, domain: DomainInfo) {
+        if self.keep_domains {
+            self.update(|report| report.domains.push(domain.clone()));
+        }
+        self.send(ReconEvent::DomainResolved(domain));
+    }
+
+    pub(crate) fn domain_unresolved(&self, domain: DomainInfo) {
+        if self.keep_domains {
+            self.update(|report| report.domains.push(domain.clone()));
+        }
+        self.send(ReconEvent::DomainUnresolved(domain));
+    }
+
+    pub(crate) fn error(&self, error: ReconError) {
+        self.update(|report| report.errors.push(error.clone()));
+        self.send(ReconEvent::Error(error));
+    }
+
+    pub(crate) fn warning(&self, warning: ReconError) {
+        self.update(|report| report.warnings.push(warning.clone()));
+        self.send(ReconEvent::Warning(warning));
+    }
+
+    /// Emit the last event of the run.
+    pub(crate) fn finish(self, result: anyhow::Result<(), ReconError>) {
+        let sender = self.sender.clone();
+        let event = match result {
+            Ok(()) => ReconEvent::Finished(self.into_report()),
+            Err(e) => ReconEvent::Failed(e),
+        };
+        if let Some(sender) = sender {
+            let _ = sender.unbounded_send(event);
+        }
+    }
+
+    pub(crate) fn into_report(self) -> ReconReport {
+        let mut report = self
+            .report
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        report.duration = self.started.elapsed();
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::channel::mpsc;
+
+    fn domain() -> DomainInfo {
+        DomainInfo::new(
+            String::from("www.example.com"),
+            String::from("A"),
+            vec![String::from("192.0.2.1")],
+        )
+    }
+
+    #[test]
+    fn test_keep_domains() {
+        let targets = [String::from("example.com")];
+        let events = Events::new(None, &targets);
+        events.domain_resolved(domain());
+        assert_eq!(1, events.into_report().domains.len());
+
+        let (sender, mut receiver) = mpsc::unbounded();
+        let events = Events::new(Some(sender), &targets);
+        events.domain_resolved(domain());
+        assert!(events.into_report().domains.is_empty());
+        assert!(matches!(
+            receiver.try_recv(),
+            Ok(ReconEvent::DomainResolved(_))
+        ));
+    }
+}
@@ -0,0 +1,145 @@
+use anyhow::anyhow;
+use reqwest::{Certificate, Client, Proxy};
+use std::time::Duration;
+use tokio_native_tls::TlsConnector;
+
+static DEFAULT_USER_AGENT: &str = concat!("domain-recon/", env!("CARGO_PKG_VERSION"));
+
+/// Settings of the HTTP client shared by every certificate provider.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HttpClientConfig {
+    /// Proxy for all the outbound traffic, ex. `http://proxy:3128` or `socks5://127.0.0.1:1080`.
+    pub proxy: Option<String>,
+    /// Path to a PEM file with additional root certificates, ex. the certificate of a TLS
+    /// intercepting proxy. Trusted by the DNS over TLS resolvers as well.
+    pub ca_bundle: Option<String>,
+    /// User-Agent header sent with every request. Defaults to `domain-recon/<version>`.
+    pub user_agent: Option<String>,
+    /// Timeout for establishing a connection.
+    pub connect_timeout: Option<Duration>,
+    /// Timeout for every read of a response, reset after each successful read. Catches stalled
+    /// responses without limiting how long a large response may take.
+    pub read_timeout: Option<Duration>,
+    /// Timeout for a whole request, from connecting until the response body is read.
+    pub request_timeout: Option<Duration>,
+}
+
+impl HttpClientConfig {
+    pub(crate) async fn build(&self) -> anyhow::Result<Client, anyhow::Error> {
+        let mut builder =
+            Client::builder().user_agent(self.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT));
+
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(
+                Proxy::all(proxy).map_err(|e| anyhow!("Invalid proxy \"{}\": {}", proxy, e))?,
+            );
+        }
+
+        if let Some(pem) = self.read_ca_bundle().await? {
+            let certificates = Certificate::from_pem_bundle(&pem)
+                .map_err(|e| anyhow!("Invalid CA bundle: {}", e))?;
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        if let Some(connect_timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+
+        if let Some(read_timeout) = self.read_timeout {
+            builder = builder.read_timeout(read_timeout);
+        }
+
+        if let Some(request_timeout) = self.request_timeout {
+            builder = builder.timeout(request_timeout);
+        }
+
+        builder.build().map_err(anyhow::Error::from)
+    }
+
+    /// TLS connector for the connections which are not sent through the HTTP client, ex. DNS over
+    /// TLS. Trusts the same additional root certificates as the HTTP client.
+    pub(crate) async fn tls_connector(&self) -> anyhow::Result<TlsConnector, anyhow::Error> {
+        let mut builder = native_tls::TlsConnector::builder();
+        if let Some(pem) = self.read_ca_bundle().await? {
+            let certificates = native_tls::Certificate::stack_from_pem(&pem)
+                .map_err(|e| anyhow!("Invalid CA bundle: {}", e))?;
+            for certificate in certificates {
+                builder.add_root_certificate(certificate);
+            }
+        }
+        Ok(TlsConnector::from(builder.build()?))
+    }
+
+    async fn read_ca_bundle(&self) -> anyhow::Result<Option<Vec<u8>>, anyhow::Error> {
+        match &self.ca_bundle {
+            Some(ca_bundle) => tokio::fs::read(ca_bundle)
+                .await
+                .map(Some)
+                .map_err(|e| anyhow!("Could not read CA bundle \"{}\": {}", ca_bundle, e)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_build_sends_user_agent() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(header("User-Agent", "recon-test"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let client = HttpClientConfig {
+            user_agent: Some(String::from("recon-test")),
+            ..HttpClientConfig::default()
+        }
+        .build()
+        .await
+        .expect("Error");
+        let response = client.get(server.uri()).send().await.expect("Error");
+        assert!(response.status().is_success());
+    }
+
+    #[tokio::test]
+    async fn test_build_read_timeout() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(2)))
+            .mount(&server)
+            .await;
+
+        let client = HttpClientConfig {
+            read_timeout: Some(Duration::from_millis(100)),
+            ..HttpClientConfig::default()
+        }
+        .build()
+        .await
+        .expect("Error");
+        let error = client.get(server.uri()).send().await.expect_err("Error");
+        assert!(error.is_timeout());
+    }
+
+    #[tokio::test]
+    async fn test_build_invalid_settings() {
+        let proxy = HttpClientConfig {
+            proxy: Some(String::from("not a proxy url")),
+            ..HttpClientConfig::default()
+        };
+        assert!(proxy.build().await.is_err());
+
+        let ca_bundle = HttpClientConfig {
+            ca_bundle: Some(String::from("/does/not/exist.pem")),
+            ..HttpClientConfig::default()
+        };
+        assert!(ca_bundle.build().await.is_err());
+    }
+}
@@ -1,81 +1,287 @@
-use crate::certificate_provider::{CertificateProvider, UnknownCertificateProvider};
-use crate::resolver::{DNSResolver, UnknownDNSResolver};
+use crate::certificate_source::{
+    CertificateSource, CertificateSourceRegistry, ProviderSettings, UnknownCertificateProvider,
+};
+use crate::http_client::HttpClientConfig;
+use crate::permutations::PermutationSettings;
+use crate::records::{DnsRecordType, UnknownRecordType};
+use crate::resolver::{DNSResolver, DnsProtocol, UnknownDNSResolver};
+use crate::retry::RetryPolicy;
+use crate::scope::Scope;
+use crate::wildcard::WildcardDnsMode;
+use crate::words::WordListTier;
 use anyhow::anyhow;
+use std::collections::HashMap;
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Debug)]
 pub struct InputArgs {
-    pub(crate) domain: String,
-    pub(crate) certificate_providers: Vec<CertificateProvider>,
-    pub(crate) file: Option<String>,
+    pub(crate) targets: Vec<String>,
+    pub(crate) certificate_providers: Vec<Arc<dyn CertificateSource>>,
+    pub(crate) provider_settings: HashMap<String, ProviderSettings>,
+    pub(crate) http_client: HttpClientConfig,
+    pub(crate) files: Vec<String>,
+    pub(crate) builtin_words: Option<WordListTier>,
+    pub(crate) expand_depth: usize,
+    pub(crate) max_expand_candidates: Option<usize>,
+    pub(crate) brute_force: bool,
+    pub(crate) brute_force_subdomains: bool,
     pub(crate) use_system_resolver: bool,
     pub(crate) dns_resolvers: Vec<DNSResolver>,
-    pub(crate) silent: bool,
+    pub(crate) dns_protocol: DnsProtocol,
+    pub(crate) record_types: Vec<DnsRecordType>,
+    pub(crate) wildcard_dns: WildcardDnsMode,
+    pub(crate) include_unresolved: bool,
+    pub(crate) scope: Scope,
+    pub(crate) permutations: Option<PermutationSettings>,
     pub(crate) config: Option<String>,
     pub(crate) number_of_parallel_requests: usize,
+    pub(crate) number_of_parallel_targets: usize,
 }
 
 impl InputArgs {
+    #[allow(clippy::too_many_arguments)]
     fn new(
-        domain: String,
-        certificate_providers: Vec<CertificateProvider>,
-        file: Option<String>,
+        targets: Vec<String>,
+        certificate_providers: Vec<Arc<dyn CertificateSource>>,
+        provider_settings: HashMap<String, ProviderSettings>,
+        http_client: HttpClientConfig,
+        files: Vec<String>,
+        builtin_words: Option<WordListTier>,
+        expand_depth: usize,
+        max_expand_candidates: Option<usize>,
+        brute_force: bool,
+        brute_force_subdomains: bool,
         use_system_resolver: bool,
         dns_resolvers: Vec<DNSResolver>,
-        silent: bool,
+        dns_protocol: DnsProtocol,
+        record_types: Vec<DnsRecordType>,
+        wildcard_dns: WildcardDnsMode,
+        include_unresolved: bool,
+        scope: Scope,
+        permutations: Option<PermutationSettings>,
         config: Option<String>,
         number_of_parallel_requests: usize,
+        number_of_parallel_targets: usize,
     ) -> anyhow::Result<InputArgs> {
         Ok(InputArgs {
-            domain,
+            targets,
             certificate_providers,
-            file,
+            provider_settings,
+            http_client,
+            files,
+            builtin_words,
+            expand_depth,
+            max_expand_candidates,
+            brute_force,
+            brute_force_subdomains,
             use_system_resolver,
             dns_resolvers,
-            silent,
+            dns_protocol,
+            record_types,
+            wildcard_dns,
+            include_unresolved,
+            scope,
+            permutations,
             config,
             number_of_parallel_requests,
+            number_of_parallel_targets,
         })
     }
+
+    /// Domains to be scanned, trimmed and without duplicates.
+    pub fn targets(&self) -> &[String] {
+        &self.targets
+    }
 }
 
 #[derive(Debug)]
 pub struct InputArgsBuilder {
-    pub(crate) domain: String, // required
+    pub(crate) targets: Vec<String>, // at least one is required
     pub(crate) certificate_providers: Vec<String>,
-    pub(crate) file: Option<String>,
+    pub(crate) registry: CertificateSourceRegistry,
+    pub(crate) provider_settings: HashMap<String, ProviderSettings>,
+    pub(crate) http_client: HttpClientConfig,
+    pub(crate) files: Vec<String>,
+    pub(crate) builtin_words: Option<WordListTier>,
+    pub(crate) expand_depth: Option<usize>,
+    pub(crate) max_expand_candidates: Option<usize>,
+    pub(crate) brute_force: bool,
+    pub(crate) brute_force_subdomains: bool,
     pub(crate) use_system_resolver: bool,
     pub(crate) dns_resolvers: Vec<String>,
-    pub(crate) silent: bool,
+    pub(crate) dns_protocol: DnsProtocol,
+    pub(crate) record_types: Vec<String>,
+    pub(crate) wildcard_dns: WildcardDnsMode,
+    pub(crate) include_unresolved: bool,
+    pub(crate) strict_scope: bool,
+    pub(crate) include_scope: Vec<String>,
+    pub(crate) exclude_scope: Vec<String>,
+    pub(crate) permutations: Option<PermutationSettings>,
     pub(crate) config: Option<String>,
     pub(crate) number_of_parallel_requests: Option<usize>,
+    pub(crate) number_of_parallel_targets: Option<usize>,
 }
 
 impl InputArgsBuilder {
     pub fn new(domain: String) -> InputArgsBuilder {
+        InputArgsBuilder::for_targets(vec![domain])
+    }
+
+    /// Builder for scanning every domain of `targets`. The targets are scanned concurrently,
+    /// sharing the HTTP client and the DNS resolver. Blank and repeated targets are skipped.
+    pub fn for_targets(targets: Vec<String>) -> InputArgsBuilder {
         InputArgsBuilder {
-            domain,
+            targets,
             certificate_providers: Vec::new(),
-            file: None,
+            registry: CertificateSourceRegistry::default(),
+            provider_settings: HashMap::new(),
+            http_client: HttpClientConfig::default(),
+            files: Vec::new(),
+            builtin_words: None,
+            expand_depth: None,
+            max_expand_candidates: None,
+            brute_force: false,
+            brute_force_subdomains: false,
             use_system_resolver: false,
             dns_resolvers: Vec::new(),
-            silent: false,
+            dns_protocol: DnsProtocol::default(),
+            record_types: Vec::new(),
+            wildcard_dns: WildcardDnsMode::default(),
+            include_unresolved: false,
+            strict_scope: true,
+            include_scope: Vec::new(),
+            exclude_scope: Vec::new(),
+            permutations: None,
             config: None,
             number_of_parallel_requests: None,
+            number_of_parallel_targets: None,
         }
     }
 
-    pub fn certificate_providers(
-        mut self,
-        certificate_providers: &Vec<String>,
-    ) -> InputArgsBuilder {
+    pub fn certificate_providers(mut self, certificate_providers: &[String]) -> InputArgsBuilder {
         self.certificate_providers
             .extend(certificate_providers.to_vec());
         self
     }
 
+    /// Registry against which the names passed to `certificate_providers` are resolved. Defaults
+    /// to the registry with the built-in sources.
+    pub fn registry(mut self, registry: CertificateSourceRegistry) -> InputArgsBuilder {
+        self.registry = registry;
+        self
+    }
+
+    /// Settings for the provider with the given name. These take precedence over the settings
+    /// from the config file.
+    pub fn provider_settings(
+        mut self,
+        provider: &str,
+        settings: ProviderSettings,
+    ) -> InputArgsBuilder {
+        self.provider_settings
+            .insert(provider.to_string(), settings);
+        self
+    }
+
+    /// Base URL of the API of the provider with the given name, ex. a caching mirror.
+    pub fn base_url(mut self, provider: &str, base_url: String) -> InputArgsBuilder {
+        self.provider_settings
+            .entry(provider.to_string())
+            .or_default()
+            .base_url = Some(base_url);
+        self
+    }
+
+    /// Retry policy for the requests sent to the provider with the given name.
+    pub fn retry_policy(mut self, provider: &str, retry_policy: RetryPolicy) -> InputArgsBuilder {
+        self.provider_settings
+            .entry(provider.to_string())
+            .or_default()
+            .retry = Some(retry_policy);
+        self
+    }
+
+    /// Proxy for all the requests sent to certificate providers, ex. `socks5://127.0.0.1:1080`.
+    pub fn proxy(mut self, proxy: Option<String>) -> InputArgsBuilder {
+        self.http_client.proxy = proxy;
+        self
+    }
+
+    /// Path to a PEM file with additional root certificates trusted by the HTTP client.
+    pub fn ca_bundle(mut self, ca_bundle: Option<String>) -> InputArgsBuilder {
+        self.http_client.ca_bundle = ca_bundle;
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: Option<String>) -> InputArgsBuilder {
+        self.http_client.user_agent = user_agent;
+        self
+    }
+
+    pub fn connect_timeout(mut self, connect_timeout: Option<Duration>) -> InputArgsBuilder {
+        self.http_client.connect_timeout = connect_timeout;
+        self
+    }
+
+    pub fn read_timeout(mut self, read_timeout: Option<Duration>) -> InputArgsBuilder {
+        self.http_client.read_timeout = read_timeout;
+        self
+    }
+
+    pub fn request_timeout(mut self, request_timeout: Option<Duration>) -> InputArgsBuilder {
+        self.http_client.request_timeout = request_timeout;
+        self
+    }
+
+    /// Add a word list. Word lists compressed with gzip or zstd are decompressed on the fly.
     pub fn file(mut self, file: Option<String>) -> InputArgsBuilder {
-        self.file = file;
+        self.files.extend(file);
+        self
+    }
+
+    /// Add word lists, read one after the other while expanding the wildcards.
+    pub fn files(mut self, files: &[String]) -> InputArgsBuilder {
+        self.files.extend(files.to_vec());
+        self
+    }
+
+    /// Word list compiled into the library, used before the words of the files.
+    pub fn builtin_words(mut self, builtin_words: Option<WordListTier>) -> InputArgsBuilder {
+        self.builtin_words = builtin_words;
+        self
+    }
+
+    /// Number of levels expanded with the word list. The words which resolved on a level are
+    /// expanded again on the next one, ex. `*.apps.example.com` gives `eu.apps.example.com` on the
+    /// first level and `web.eu.apps.example.com` on the second. Defaults to 1.
+    pub fn expand_depth(mut self, expand_depth: usize) -> InputArgsBuilder {
+        self.expand_depth = Some(expand_depth);
+        self
+    }
+
+    /// Maximum number of candidates expanded with the word list for a target, on all levels
+    /// together. Unlimited by default.
+    pub fn max_expand_candidates(
+        mut self,
+        max_expand_candidates: Option<usize>,
+    ) -> InputArgsBuilder {
+        self.max_expand_candidates = max_expand_candidates;
+        self
+    }
+
+    /// Expand `*.<target>` with the word list as well, even if the certificates contain no
+    /// wildcard names.
+    pub fn brute_force(mut self, brute_force: bool) -> InputArgsBuilder {
+        self.brute_force = brute_force;
+        self
+    }
+
+    /// Expand the wildcard of every domain resolved from the certificates with the word list as
+    /// well, ex. `*.api.example.com` for `api.example.com`.
+    pub fn brute_force_subdomains(mut self, brute_force_subdomains: bool) -> InputArgsBuilder {
+        self.brute_force_subdomains = brute_force_subdomains;
         self
     }
 
@@ -84,13 +290,67 @@ impl InputArgsBuilder {
         self
     }
 
-    pub fn dns_resolvers(mut self, dns_resolvers: &Vec<String>) -> InputArgsBuilder {
+    pub fn dns_resolvers(mut self, dns_resolvers: &[String]) -> InputArgsBuilder {
         self.dns_resolvers.extend(dns_resolvers.to_vec());
         self
     }
 
-    pub fn silent(mut self, silent: bool) -> InputArgsBuilder {
-        self.silent = silent;
+    /// Protocol used for the nameservers which are not given with their own protocol.
+    pub fn dns_protocol(mut self, dns_protocol: DnsProtocol) -> InputArgsBuilder {
+        self.dns_protocol = dns_protocol;
+        self
+    }
+
+    /// Additional DNS record types queried for every resolved domain, ex. "cname" or "mx".
+    pub fn record_types(mut self, record_types: &[String]) -> InputArgsBuilder {
+        self.record_types.extend(record_types.to_vec());
+        self
+    }
+
+    /// What to do with the domains matching the wildcard DNS record of their zone.
+    pub fn wildcard_dns(mut self, wildcard_dns: WildcardDnsMode) -> InputArgsBuilder {
+        self.wildcard_dns = wildcard_dns;
+        self
+    }
+
+    /// Include the names found in certificates which could not be resolved, together with the
+    /// status of their lookup.
+    pub fn include_unresolved(mut self, include_unresolved: bool) -> InputArgsBuilder {
+        self.include_unresolved = include_unresolved;
+        self
+    }
+
+    /// Keep only the names under the registrable domain of the target, ex. `example.co.uk` for
+    /// `www.example.co.uk`, besides the included ones. Enabled by default.
+    pub fn strict_scope(mut self, strict_scope: bool) -> InputArgsBuilder {
+        self.strict_scope = strict_scope;
+        self
+    }
+
+    /// Patterns of names kept even if they are not under the registrable domain of the target,
+    /// ex. `*.example-cdn.net`.
+    pub fn include_scope(mut self, patterns: &[String]) -> InputArgsBuilder {
+        self.include_scope.extend(patterns.to_vec());
+        self
+    }
+
+    /// Patterns of names which are never resolved, ex. `*.dev.example.com`.
+    pub fn exclude_scope(mut self, patterns: &[String]) -> InputArgsBuilder {
+        self.exclude_scope.extend(patterns.to_vec());
+        self
+    }
+
+    /// Resolve the permutations of the domains which were resolved, ex. `dev-api` or `api2` for
+    /// `api`. Disabled by default.
+    pub fn permutations(mut self, permutations: Option<PermutationSettings>) -> InputArgsBuilder {
+        self.permutations = permutations;
+        self
+    }
+
+    #[deprecated(
+        note = "nothing is printed by the library, render the events of `run_stream` instead"
+    )]
+    pub fn silent(self, _silent: bool) -> InputArgsBuilder {
         self
     }
 
@@ -99,6 +359,7 @@ impl InputArgsBuilder {
         self
     }
 
+    /// Number of DNS lookups in flight, shared by all the targets scanned at the same time.
     pub fn number_of_parallel_requests(
         mut self,
         number_of_parallel_requests: usize,
@@ -107,12 +368,41 @@ impl InputArgsBuilder {
         self
     }
 
+    /// Number of targets scanned at the same time.
+    pub fn number_of_parallel_targets(
+        mut self,
+        number_of_parallel_targets: usize,
+    ) -> InputArgsBuilder {
+        self.number_of_parallel_targets = Some(number_of_parallel_targets);
+        self
+    }
+
     pub fn build(self) -> anyhow::Result<InputArgs> {
-        let certificate_providers: Result<Vec<CertificateProvider>, UnknownCertificateProvider> =
-            self.certificate_providers
-                .iter()
-                .map(|provider| CertificateProvider::from_str(provider))
-                .collect();
+        if (self.brute_force || self.brute_force_subdomains)
+            && self.files.is_empty()
+            && self.builtin_words.is_none()
+        {
+            return Err(anyhow!("Brute forcing requires a word list!"));
+        }
+
+        let mut targets: Vec<String> = vec![];
+        for target in self.targets.iter().map(|target| target.trim()) {
+            if !target.is_empty() && !targets.iter().any(|known| known == target) {
+                targets.push(target.to_string());
+            }
+        }
+        if targets.is_empty() {
+            return Err(anyhow!("No domain to scan!"));
+        }
+
+        let certificate_providers: Result<
+            Vec<Arc<dyn CertificateSource>>,
+            UnknownCertificateProvider,
+        > = self
+            .certificate_providers
+            .iter()
+            .map(|provider| self.registry.resolve(provider))
+            .collect();
 
         let dns_input: Result<Vec<DNSResolver>, UnknownDNSResolver> = if !self.use_system_resolver {
             self.dns_resolvers
@@ -122,16 +412,35 @@ impl InputArgsBuilder {
         } else {
             Ok(vec![])
         };
-        let n = self.number_of_parallel_requests.unwrap_or_else(|| 20);
+        let record_types: Result<Vec<DnsRecordType>, UnknownRecordType> = self
+            .record_types
+            .iter()
+            .map(|record_type| DnsRecordType::from_str(record_type))
+            .collect();
+        let n = self.number_of_parallel_requests.unwrap_or(20);
+        let parallel_targets = self.number_of_parallel_targets.unwrap_or(4).max(1);
         InputArgs::new(
-            self.domain,
+            targets,
             certificate_providers.map_err(|e| anyhow!(e))?,
-            self.file,
+            self.provider_settings,
+            self.http_client,
+            self.files,
+            self.builtin_words,
+            self.expand_depth.unwrap_or(1),
+            self.max_expand_candidates,
+            self.brute_force,
+            self.brute_force_subdomains,
             self.use_system_resolver,
             dns_input.map_err(|e| anyhow!(e))?,
-            self.silent,
+            self.dns_protocol,
+            record_types.map_err(|e| anyhow!(e))?,
+            self.wildcard_dns,
+            self.include_unresolved,
+            Scope::new(self.strict_scope, &self.include_scope, &self.exclude_scope),
+            self.permutations,
             self.config,
             n,
+            parallel_targets,
         )
     }
 }
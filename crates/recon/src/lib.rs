@@ -1,49 +1,109 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Debug;
 use std::future;
-use std::future::Future;
 use std::path::Path;
-use std::pin::Pin;
+use std::pin::pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 
-use crate::censys_fetcher::CensysConfig;
 use addr::parse_domain_name;
 use anyhow::anyhow;
 use async_std_resolver::lookup_ip::LookupIp;
 use async_std_resolver::{
     config, resolver, resolver_from_system_conf, AsyncStdResolver, ResolveError,
 };
-use console::{style, Emoji};
+use futures::channel::mpsc;
 use futures::future::join_all;
-use futures::{FutureExt, StreamExt};
-use tokio::fs::{read_to_string, File};
-use tokio::io::{self, AsyncBufReadExt, BufReader};
+use futures::{stream, FutureExt, Stream, StreamExt};
+use tokio::fs::read_to_string;
+use tokio::io;
 
-use crate::certificate_provider::CertificateProvider;
-use crate::certificate_provider::CertificateProvider::{Censys, CertSpotter};
-use crate::certspotter_fetcher::CertSpotterConfig;
+pub use crate::certificate_source::{
+    CertificateRecord, CertificateSource, CertificateSourceRegistry, FetchContext, FetchResult,
+    ProviderSettings,
+};
+use crate::dns_client::DnsClient;
+use crate::events::Events;
+pub use crate::events::ReconEvent;
+pub use crate::http_client::HttpClientConfig;
 pub use crate::input_args::{InputArgs, InputArgsBuilder};
+pub use crate::permutations::{PermutationSettings, DEFAULT_PERMUTATION_WORDS};
+use crate::records::lookup_records;
+pub use crate::records::{DnsRecordType, UnknownRecordType};
+pub use crate::report::{ProviderSummary, ReconError, ReconReport, Stage};
 use crate::resolver::DNSResolver;
+pub use crate::resolver::DnsProtocol;
+pub use crate::retry::RetryPolicy;
+pub use crate::scope::OutOfScope;
+pub use crate::status::LookupStatus;
+pub use crate::wildcard::WildcardDnsMode;
+use crate::wildcard::WildcardZones;
+pub use crate::words::WordListTier;
+use crate::words::WordLists;
+pub use async_trait::async_trait;
+pub use reqwest;
 use serde::{Deserialize, Serialize};
 
 mod censys_fetcher;
-mod certificate_provider;
+mod certificate_source;
 mod certspotter_fetcher;
-mod config_validator;
+mod credentials;
 mod crtsh_fetcher;
+mod dns_client;
+mod events;
+mod http_client;
 mod input_args;
+mod permutations;
+mod records;
+mod report;
 mod resolver;
+mod retry;
+mod scope;
+mod status;
+mod wildcard;
+mod words;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 struct DomainReconConfig {
-    censys: Option<Vec<CensysConfig>>,
-    certspotter: Option<Vec<CertSpotterConfig>>,
+    // Settings which are not credentials, keyed by the name of the certificate source
+    #[serde(default)]
+    settings: HashMap<String, ProviderSettings>,
+    // Sections of the config file keyed by the name of the certificate source they belong to
+    #[serde(flatten)]
+    sources: HashMap<String, serde_json::Value>,
 }
 
-#[derive(Debug)]
+impl DomainReconConfig {
+    fn source_config(&self, name: &str) -> Option<&serde_json::Value> {
+        self.sources.get(name)
+    }
+
+    fn source_settings(&self, name: &str) -> Option<&ProviderSettings> {
+        self.settings.get(name)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct DomainInfo {
     pub name: String,
     pub domain_type: String,
     pub ip_addresses: Vec<String>,
+    /// Names of the certificate sources which returned the domain, or the wildcard it was expanded
+    /// from.
+    pub providers: Vec<String>,
+    /// Certificates in which the domain, or the wildcard it was expanded from, was found.
+    pub certificates: Vec<CertificateRecord>,
+    /// Additional DNS records of the domain, only of the record types requested in the input
+    /// args.
+    pub records: BTreeMap<DnsRecordType, Vec<String>>,
+    /// The addresses of the domain are the ones answered by the wildcard DNS record of its zone,
+    /// the domain may not exist on its own.
+    pub wildcard_dns: bool,
+    /// Whether the addresses of the domain were found. Domains with another status are included
+    /// only on request, and without addresses.
+    pub status: LookupStatus,
+    /// Target of the scan which found the domain.
+    pub target: String,
 }
 
 impl DomainInfo {
@@ -52,17 +112,81 @@ impl DomainInfo {
             name,
             domain_type,
             ip_addresses,
+            providers: vec![],
+            certificates: vec![],
+            records: BTreeMap::new(),
+            wildcard_dns: false,
+            status: LookupStatus::Resolved,
+            target: String::new(),
+        }
+    }
+
+    /// Domain whose addresses could not be resolved, ex. a name from a stale certificate.
+    pub fn unresolved(name: String, status: LookupStatus) -> DomainInfo {
+        DomainInfo {
+            status,
+            ..DomainInfo::new(name, String::new(), vec![])
         }
     }
+
+    /// Attach the certificates the domain was found in. The providers are derived from them.
+    pub fn with_certificates(mut self, certificates: Vec<CertificateRecord>) -> DomainInfo {
+        let mut providers = certificates
+            .iter()
+            .map(|certificate| certificate.provider.clone())
+            .collect::<Vec<String>>();
+        providers.sort();
+        providers.dedup();
+        self.providers = providers;
+        self.certificates = certificates;
+        self
+    }
+
+    pub fn with_records(mut self, records: BTreeMap<DnsRecordType, Vec<String>>) -> DomainInfo {
+        self.records = records;
+        self
+    }
+
+    pub fn with_wildcard_dns(mut self, wildcard_dns: bool) -> DomainInfo {
+        self.wildcard_dns = wildcard_dns;
+        self
+    }
+
+    pub fn with_target(mut self, target: &str) -> DomainInfo {
+        self.target = target.to_string();
+        self
+    }
 }
 
-static LOOKING_GLASS: Emoji<'_, '_> = Emoji("🔍  ", "*");
-static CLIP: Emoji<'_, '_> = Emoji("🔗  ", "*");
-static SPARKLE: Emoji<'_, '_> = Emoji("✨ ", "*");
+/// Run the reconnaissance and return the report of the whole run. Nothing is printed, use
+/// `run_stream` for following the progress of the run.
+pub async fn run(input_args: InputArgs) -> anyhow::Result<ReconReport, ReconError> {
+    let events = Events::new(None, &input_args.targets);
+    recon(input_args, &events).await?;
+    Ok(events.into_report())
+}
 
-static PROVIDERS_WITH_CONFIG: [CertificateProvider; 2] = [Censys, CertSpotter];
+/// Run the reconnaissance, returning a stream of the events of the run. The stream ends with
+/// either `ReconEvent::Finished` or `ReconEvent::Failed`. The run makes progress only while the
+/// stream is polled. The domains are not kept in the final report, so that the memory used does
+/// not grow with the number of domains found.
+pub fn run_stream(input_args: InputArgs) -> impl Stream<Item = ReconEvent> {
+    let (sender, receiver) = mpsc::unbounded();
+    let run = async move {
+        let events = Events::new(Some(sender), &input_args.targets);
+        let result = recon(input_args, &events).await;
+        events.finish(result);
+    };
 
-pub async fn run(input_args: InputArgs) -> anyhow::Result<Vec<DomainInfo>> {
+    // The run is polled together with the receiving end of its events. The sender is dropped when
+    // the run is over, which ends the stream once every event was received.
+    stream::select(
+        receiver,
+        run.into_stream().filter_map(|_| future::ready(None)),
+    )
+}
+
+async fn recon(input_args: InputArgs, events: &Events) -> anyhow::Result<(), ReconError> {
     // Get the default $HOME path depending on the operating system
     let default_home_path = match home::home_dir() {
         Some(path) => path
@@ -73,109 +197,229 @@ pub async fn run(input_args: InputArgs) -> anyhow::Result<Vec<DomainInfo>> {
     };
 
     // Build the path for the config file
-    let config_path = input_args.config.map_or(default_home_path, |path_str| {
-        Path::new(&path_str).to_path_buf()
-    });
+    let config_path = input_args
+        .config
+        .as_ref()
+        .map_or(default_home_path, |path_str| {
+            Path::new(path_str).to_path_buf()
+        });
 
     // Attempt to read the config file. The config file may not be present
     let config = if config_path.exists() {
-        Some(read_config(config_path).await?)
+        Some(
+            read_config(config_path)
+                .await
+                .map_err(|e| ReconError::Config(e.to_string()))?,
+        )
     } else {
         None
     };
 
     validate_config(&config, &input_args.certificate_providers)?;
+    let config = config.unwrap_or_default();
+    validate_settings(
+        &config,
+        &input_args.certificate_providers,
+        &input_args.provider_settings,
+    )?;
 
-    let steps = if input_args.file.is_none() { 2 } else { 3 };
+    // The HTTP client, the DNS client and the word lists are shared by every target
+    let http_client = input_args
+        .http_client
+        .build()
+        .await
+        .map_err(|e| ReconError::HttpClient(e.to_string()))?;
+    let dns_client = build_dns_client(&input_args, &http_client)
+        .await
+        .map_err(|e| ReconError::DnsResolver(e.to_string()))?;
+    let words = if input_args.files.is_empty() && input_args.builtin_words.is_none() {
+        None
+    } else {
+        Some(WordLists::open(input_args.builtin_words, &input_args.files).await?)
+    };
 
-    if !input_args.silent {
-        println!(
-            "{} {}{}",
-            style(format!("[1/{}]", steps)).bold().dim(),
-            LOOKING_GLASS,
-            style("Fetching certificates...").bold()
-        );
-    }
+    let scans = input_args
+        .targets
+        .iter()
+        .map(|target| {
+            scan_target(
+                target,
+                &input_args,
+                &config,
+                &http_client,
+                &dns_client,
+                words.as_ref(),
+                events,
+            )
+        })
+        .collect::<Vec<_>>();
+    stream::iter(scans)
+        .buffer_unordered(input_args.number_of_parallel_targets)
+        .collect::<Vec<()>>()
+        .await;
 
-    let (wildcards, fqdns) =
-        fetch_certificates(&input_args.certificate_providers, input_args.domain, config).await?;
-    let dns_resolver =
-        build_dns_resolver(input_args.use_system_resolver, &input_args.dns_resolvers).await?;
+    Ok(())
+}
 
-    if wildcards.is_empty() && fqdns.is_empty() {
-        if !input_args.silent {
-            println!(
-                "\n{} {}{}",
-                style(format!("[!/{}]", steps)).bold().dim(),
-                CLIP,
-                style("No domains found. Closing...").bold()
-            );
-        }
-        return Ok(vec![]);
+async fn scan_target(
+    target: &str,
+    input_args: &InputArgs,
+    config: &DomainReconConfig,
+    http_client: &reqwest::Client,
+    dns_client: &DnsClient,
+    words: Option<&WordLists>,
+    events: &Events,
+) {
+    let steps = 2 + usize::from(words.is_some()) + usize::from(input_args.permutations.is_some());
+
+    events.stage_started(target, Stage::Fetch, 1, steps);
+    let certificates = fetch_certificates(
+        http_client,
+        &input_args.certificate_providers,
+        &input_args.provider_settings,
+        target,
+        config,
+        events,
+    )
+    .await;
+
+    // Names out of the scope of the target are only reported
+    let (certificates, out_of_scope): (HashMap<_, _>, HashMap<_, _>) = certificates
+        .into_iter()
+        .partition(|(name, _)| input_args.scope.contains(target, name));
+    if !out_of_scope.is_empty() {
+        let mut names = out_of_scope.into_keys().collect::<Vec<String>>();
+        names.sort();
+        events.out_of_scope(OutOfScope {
+            target: target.to_string(),
+            names,
+        });
     }
 
-    if !input_args.silent {
-        println!(
-            "\n{} {}{}",
-            style(format!("[2/{}]", steps)).bold().dim(),
-            CLIP,
-            style("Extracting valid domains...").bold()
-        );
+    let (wildcards, fqdns): (HashSet<String>, HashSet<String>) = certificates
+        .keys()
+        .cloned()
+        .partition(|name| name.starts_with('*'));
+
+    // Brute forcing goes on without any name from the certificates
+    if wildcards.is_empty() && fqdns.is_empty() && !input_args.brute_force {
+        events.no_domains_found(target);
+        return;
     }
 
-    let mut resolvable = get_resolvable_domains(
-        &fqdns,
-        &dns_resolver,
-        input_args.silent,
-        input_args.number_of_parallel_requests,
+    events.stage_started(target, Stage::Resolve, 2, steps);
+    let wildcard_zones =
+        WildcardZones::detect(dns_client, &fqdns, input_args.number_of_parallel_requests).await;
+    let mut resolved = resolve_domains(
+        target,
+        stream::iter(fqdns.iter().cloned()),
+        &wildcard_zones,
+        &certificates,
+        dns_client,
+        input_args,
+        input_args.include_unresolved,
+        Stage::Resolve,
+        events,
     )
     .await;
 
-    // If there is an input file for words, use it for extending domains, otherwise move forward
-    if let Some(words_file_str) = input_args.file {
-        let words_path = Path::new(&words_file_str);
-        if !input_args.silent {
-            println!(
-                "\n{} {}{}",
-                style(format!("[3/{}]", steps)).bold().dim(),
-                SPARKLE,
-                style("Expanding wildcards...").bold()
-            );
+    // If there is a word list, built-in or from files, use it for extending domains, otherwise
+    // move forward. The labels which resolved become the parents of the next level, until the
+    // depth or the budget is exhausted. The candidates are generated while they are resolved, the
+    // word lists being read again for every level.
+    if let Some(words) = words {
+        events.stage_started(target, Stage::Expand, 3, steps);
+        let mut budget = input_args.max_expand_candidates.unwrap_or(usize::MAX);
+        let mut parents = wildcards;
+        if input_args.brute_force {
+            parents.insert(format!("*.{}", target));
         }
-
-        let words = read_words(words_path).await?;
-        if let Ok(domains) = expand_wildcards(&wildcards, &fqdns, &words).await {
-            resolvable.extend(
-                get_resolvable_domains(
-                    &domains,
-                    &dns_resolver,
-                    input_args.silent,
-                    input_args.number_of_parallel_requests,
-                )
-                .await,
-            );
+        if input_args.brute_force_subdomains {
+            parents.extend(resolved.iter().map(|domain| format!("*.{}", domain)));
+        }
+        for _ in 0..input_args.expand_depth {
+            if parents.is_empty() || budget == 0 {
+                break;
+            }
+            let wildcard_zones =
+                WildcardZones::detect(dns_client, &parents, input_args.number_of_parallel_requests)
+                    .await;
+            let mut sorted_parents = parents.into_iter().collect::<Vec<String>>();
+            sorted_parents.sort();
+            let words = words.words().filter_map(|word| {
+                future::ready(match word {
+                    Ok(word) => Some(word),
+                    Err(e) => {
+                        events.error(e);
+                        None
+                    }
+                })
+            });
+            let generated = AtomicUsize::new(0);
+            // Only the candidates in scope, and not resolved on an earlier level, count against
+            // the budget
+            let domains = expand_wildcards(&sorted_parents, &fqdns, words)
+                .filter(|candidate| {
+                    future::ready(
+                        !resolved.contains(candidate)
+                            && input_args.scope.contains(target, candidate),
+                    )
+                })
+                .take(budget)
+                .inspect(|_| {
+                    generated.fetch_add(1, Ordering::Relaxed);
+                });
+            let found = resolve_domains(
+                target,
+                domains,
+                &wildcard_zones,
+                &certificates,
+                dns_client,
+                input_args,
+                false,
+                Stage::Expand,
+                events,
+            )
+            .await;
+            budget -= generated.into_inner();
+            parents = found.iter().map(|domain| format!("*.{}", domain)).collect();
+            resolved.extend(found);
         }
     }
 
-    Ok(resolvable
-        .iter()
-        .map(|lookup| {
-            let records = lookup
-                .iter()
-                .map(|record| record.to_string())
-                .collect::<Vec<String>>();
-            DomainInfo::new(
-                lookup.query().name().to_string(),
-                lookup.query().query_type().to_string(),
-                records,
-            )
-        })
-        .collect())
+    // Look for the neighbours of the domains which were resolved, ex. `api2` next to `api`
+    if let Some(permutations) = &input_args.permutations {
+        events.stage_started(target, Stage::Permute, steps, steps);
+        let candidates = permutations
+            .permutations(&resolved)
+            .into_iter()
+            .filter(|candidate| !fqdns.contains(candidate))
+            .filter(|candidate| input_args.scope.contains(target, candidate))
+            .collect::<HashSet<String>>();
+        let wildcard_zones = WildcardZones::detect(
+            dns_client,
+            &candidates,
+            input_args.number_of_parallel_requests,
+        )
+        .await;
+        resolve_domains(
+            target,
+            stream::iter(candidates),
+            &wildcard_zones,
+            &certificates,
+            dns_client,
+            input_args,
+            false,
+            Stage::Permute,
+            events,
+        )
+        .await;
+    }
 }
 
 async fn read_config<P: AsRef<Path>>(path: P) -> anyhow::Result<DomainReconConfig, io::Error> {
     let contents = read_to_string(path).await?;
-    let config = serde_json::from_str::<DomainReconConfig>(&*contents)?;
+    let config = serde_json::from_str::<DomainReconConfig>(&contents)?;
     Ok(config)
 }
 
@@ -183,33 +427,29 @@ async fn read_config<P: AsRef<Path>>(path: P) -> anyhow::Result<DomainReconConfi
 // if the requested providers have secrets in the configuration file.
 fn validate_config(
     config: &Option<DomainReconConfig>,
-    providers: &Vec<CertificateProvider>,
-) -> anyhow::Result<(), anyhow::Error> {
+    providers: &[Arc<dyn CertificateSource>],
+) -> anyhow::Result<(), ReconError> {
     match config {
         None => {
             if providers
                 .iter()
-                .any(move |provider| PROVIDERS_WITH_CONFIG.contains(provider))
+                .any(|provider| provider.config_schema().is_some())
             {
-                return Err(anyhow!("No config file provided!"));
+                return Err(ReconError::MissingConfig);
             }
         }
         Some(recon_config) => {
-            // Validate the config file, return if there are errors
-            let validation_results = providers
-                .into_iter()
-                .map(|certificate_provider| {
-                    certificate_provider
-                        .config_validator()
-                        .validate(&recon_config)
-                })
-                .filter(|result| result.is_err())
-                .collect::<Vec<_>>();
-
-            if !validation_results.is_empty() {
-                // Return only the first error for now!
-                let error = validation_results.into_iter().next().unwrap();
-                return Err(anyhow!(error.unwrap_err().to_string()));
+            // Validate the config file, return only the first error for now!
+            if let Some(error) = providers.iter().find_map(|provider| {
+                provider
+                    .validate(recon_config.source_config(provider.name()))
+                    .err()
+                    .map(|e| ReconError::ProviderConfig {
+                        provider: provider.name().to_string(),
+                        message: e.to_string(),
+                    })
+            }) {
+                return Err(error);
             }
         }
     }
@@ -217,229 +457,722 @@ fn validate_config(
     Ok(())
 }
 
-async fn fetch_certificates(
-    certificate_providers: &Vec<CertificateProvider>,
-    domain: String,
-    optional_config: Option<DomainReconConfig>,
-) -> anyhow::Result<(HashSet<String>, HashSet<String>), anyhow::Error> {
-    type PinFutureObj<Output> = Pin<Box<dyn Future<Output = Output>>>;
-
-    let mut wildcards = HashSet::new();
-    let mut fqdns = HashSet::new();
-
-    let mut futures: Vec<PinFutureObj<anyhow::Result<(Vec<String>, Vec<String>), anyhow::Error>>> =
-        Vec::new();
+// Validate the settings of every requested provider, ex. a page limit of 0 would fetch nothing
+fn validate_settings(
+    config: &DomainReconConfig,
+    providers: &[Arc<dyn CertificateSource>],
+    provider_settings: &HashMap<String, ProviderSettings>,
+) -> anyhow::Result<(), ReconError> {
+    for provider in providers {
+        settings_for(provider.name(), provider_settings, config)
+            .validate()
+            .map_err(|e| ReconError::ProviderConfig {
+                provider: provider.name().to_string(),
+                message: e.to_string(),
+            })?;
+    }
+    Ok(())
+}
 
-    if certificate_providers.contains(&CertificateProvider::CertSh) {
-        futures.push(Box::pin(crtsh_fetcher::fetch(domain.clone())));
+// Settings given in the input args take precedence over the ones from the config file
+fn settings_for(
+    name: &str,
+    provider_settings: &HashMap<String, ProviderSettings>,
+    config: &DomainReconConfig,
+) -> ProviderSettings {
+    let from_input = provider_settings.get(name).cloned().unwrap_or_default();
+    match config.source_settings(name) {
+        Some(from_config) => from_input.or(from_config),
+        None => from_input,
     }
+}
 
-    if let Some(config) = optional_config {
-        if certificate_providers.contains(&Censys) {
-            if let Some(censys) = config.censys {
-                futures.push(Box::pin(censys_fetcher::fetch(domain.clone(), censys)));
-            }
-        }
+async fn fetch_certificates(
+    http_client: &reqwest::Client,
+    certificate_providers: &[Arc<dyn CertificateSource>],
+    provider_settings: &HashMap<String, ProviderSettings>,
+    domain: &str,
+    config: &DomainReconConfig,
+    events: &Events,
+) -> HashMap<String, Vec<CertificateRecord>> {
+    let mut certificates: HashMap<String, Vec<CertificateRecord>> = HashMap::new();
 
-        if certificate_providers.contains(&CertSpotter) {
-            if let Some(certspotter) = config.certspotter {
-                futures.push(Box::pin(certspotter_fetcher::fetch(
-                    domain.clone(),
-                    certspotter,
-                )));
-            }
-        }
-    }
+    let settings = certificate_providers
+        .iter()
+        .map(|provider| settings_for(provider.name(), provider_settings, config))
+        .collect::<Vec<_>>();
 
-    for result in join_all(futures).await {
+    let futures = certificate_providers
+        .iter()
+        .zip(settings.iter())
+        .map(|(provider, settings)| {
+            provider.fetch(
+                domain,
+                FetchContext {
+                    client: http_client,
+                    config: config.source_config(provider.name()),
+                    settings,
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+
+    for (provider, result) in certificate_providers.iter().zip(join_all(futures).await) {
         match result {
-            Ok((w, f)) => {
-                wildcards.extend(w);
-                fqdns.extend(f);
+            Ok(FetchResult {
+                names,
+                pages,
+                errors,
+            }) => {
+                for e in errors {
+                    events.error(ReconError::ProviderIncomplete {
+                        target: domain.to_string(),
+                        provider: provider.name().to_string(),
+                        message: e,
+                    });
+                }
+                events.provider_finished(ProviderSummary {
+                    target: domain.to_string(),
+                    provider: provider.name().to_string(),
+                    names: names.len(),
+                    pages,
+                });
+                for (name, found_in) in names {
+                    certificates.entry(name).or_default().extend(found_in);
+                }
             }
             Err(e) => {
-                println!("Could not fetch from provider. Error: {}", e);
+                events.error(ReconError::ProviderFailed {
+                    target: domain.to_string(),
+                    provider: provider.name().to_string(),
+                    message: e.to_string(),
+                });
             }
         };
     }
 
-    Ok((wildcards, fqdns))
+    certificates
+}
+
+// Certificates of a resolved domain. Domains obtained by expanding a wildcard inherit the
+// certificates of the wildcard.
+fn certificates_for(
+    certificates: &HashMap<String, Vec<CertificateRecord>>,
+    name: &str,
+) -> Vec<CertificateRecord> {
+    let name = name.trim_end_matches('.');
+    if let Some(found_in) = certificates.get(name) {
+        return found_in.clone();
+    }
+    name.split_once('.')
+        .and_then(|(_, parent)| certificates.get(&format!("*.{}", parent)))
+        .cloned()
+        .unwrap_or_default()
+}
+
+// Encrypted resolvers are queried through their own client, the rest of them through a single
+// resolver, which is also the fallback of the encrypted ones
+async fn build_dns_client(
+    input_args: &InputArgs,
+    http_client: &reqwest::Client,
+) -> anyhow::Result<DnsClient, anyhow::Error> {
+    let mut plain = vec![];
+    let mut encrypted = vec![];
+    for resolver in &input_args.dns_resolvers {
+        match resolver {
+            DNSResolver::Encrypted(endpoint) => encrypted.push(endpoint.clone()),
+            DNSResolver::File(path) => {
+                let contents = read_to_string(path)
+                    .await
+                    .map_err(|e| anyhow!("Could not read resolver file \"{}\": {}", path, e))?;
+                for resolver in DNSResolver::parse_file(&contents)? {
+                    match resolver {
+                        DNSResolver::Encrypted(endpoint) => encrypted.push(endpoint),
+                        resolver => plain.push(resolver),
+                    }
+                }
+            }
+            resolver => plain.push(resolver.clone()),
+        }
+    }
+
+    let plain_resolver = if input_args.use_system_resolver || !plain.is_empty() {
+        Some(
+            build_dns_resolver(
+                input_args.use_system_resolver,
+                &plain,
+                input_args.dns_protocol,
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
+    let tls_connector = if encrypted.is_empty() {
+        tokio_native_tls::TlsConnector::from(native_tls::TlsConnector::new()?)
+    } else {
+        input_args.http_client.tls_connector().await?
+    };
+    Ok(DnsClient::new(
+        plain_resolver,
+        encrypted,
+        http_client.clone(),
+        tls_connector,
+        input_args.http_client.request_timeout,
+        input_args.number_of_parallel_requests,
+    ))
 }
 
 async fn build_dns_resolver(
     use_system_resolver: bool,
-    dns_resolvers: &Vec<DNSResolver>,
-) -> anyhow::Result<AsyncStdResolver, ResolveError> {
+    dns_resolvers: &[DNSResolver],
+    dns_protocol: DnsProtocol,
+) -> anyhow::Result<AsyncStdResolver, anyhow::Error> {
     if use_system_resolver {
-        return resolver_from_system_conf().await;
+        return Ok(resolver_from_system_conf().await?);
     }
 
     // Add all the available nameservers to the resolver
     let mut dns_cfg = config::ResolverConfig::new();
+    let protocol = dns_protocol.protocol();
 
+    // The presets contain every nameserver both for UDP and TCP
+    let mut add_preset = |group: config::NameServerConfigGroup| {
+        for ns in group.iter().filter(|ns| ns.protocol == protocol) {
+            dns_cfg.add_name_server(ns.clone());
+        }
+    };
+
+    let mut custom = vec![];
     for resolver in dns_resolvers {
         match resolver {
-            DNSResolver::Google => {
-                for ns in config::NameServerConfigGroup::google().to_vec() {
-                    dns_cfg.add_name_server(ns);
-                }
-            }
-            DNSResolver::CloudFlare => {
-                for ns in config::NameServerConfigGroup::cloudflare().to_vec() {
-                    dns_cfg.add_name_server(ns);
-                }
-            }
-            DNSResolver::Quad9 => {
-                for ns in config::NameServerConfigGroup::quad9().to_vec() {
-                    dns_cfg.add_name_server(ns);
-                }
-            }
+            DNSResolver::Google => add_preset(config::NameServerConfigGroup::google()),
+            DNSResolver::CloudFlare => add_preset(config::NameServerConfigGroup::cloudflare()),
+            DNSResolver::Quad9 => add_preset(config::NameServerConfigGroup::quad9()),
+            DNSResolver::Custom { address, protocol } => custom.push((*address, *protocol)),
+            // Resolver files and encrypted resolvers are handled by the DNS client
+            DNSResolver::File(_) | DNSResolver::Encrypted(_) => {}
         }
     }
 
+    for (address, custom_protocol) in custom {
+        dns_cfg.add_name_server(config::NameServerConfig::new(
+            address,
+            custom_protocol.unwrap_or(dns_protocol).protocol(),
+        ));
+    }
+
     let resolver_cfg = config::ResolverOpts::default();
     let resolver = resolver(dns_cfg, resolver_cfg).await;
     Ok(resolver)
 }
 
-async fn read_words<P: AsRef<Path>>(words_path: P) -> anyhow::Result<HashSet<String>, io::Error> {
-    let mut lines = BufReader::new(File::open(words_path).await?).lines();
-    let mut words: HashSet<String> = HashSet::new();
-    while let Some(line) = lines.next_line().await? {
-        words.insert(line.trim().to_string());
-    }
-    Ok(words)
-}
-
-async fn expand_wildcards(
-    wildcards: &HashSet<String>,
-    fqdns: &HashSet<String>,
-    words: &HashSet<String>,
-) -> anyhow::Result<HashSet<String>, io::Error> {
-    let mut potential_domains: HashSet<String> = HashSet::new();
-    for word in words {
-        potential_domains.extend(
-            wildcards
+// Replace the `*` of every parent with one word at a time, so that only the candidates of the
+// words being resolved are held in memory. Names already known are left out.
+fn expand_wildcards<'a>(
+    parents: &'a [String],
+    fqdns: &'a HashSet<String>,
+    words: impl Stream<Item = String> + 'a,
+) -> impl Stream<Item = String> + 'a {
+    words.flat_map(move |word| {
+        stream::iter(
+            parents
                 .iter()
-                .map(|domain| domain.replace('*', word))
+                .map(|parent| parent.replace('*', &word))
                 .filter(|domain| !fqdns.contains(domain))
-                .collect::<HashSet<String>>(),
-        );
-    }
-    Ok(potential_domains)
+                .collect::<Vec<String>>(),
+        )
+    })
 }
 
-async fn get_resolvable_domains(
-    domains: &HashSet<String>,
-    dns_resolver: &AsyncStdResolver,
-    silent: bool,
-    number_of_parallel_request: usize,
-) -> Vec<LookupIp> {
-    let mut result: Vec<anyhow::Result<LookupIp, ResolveError>> = vec![];
+// Resolve the domains, emitting the ones with records as soon as they are resolved. The domains
+// are pulled from the stream only as fast as they are resolved. Lookups failing for a reason
+// other than a missing record, ex. a timeout, are reported as warnings.
+// Domains matching the wildcard DNS record of their zone are flagged, or dropped if they were
+// generated, ex. from the word list, and the mode says so. Domains which could not be resolved
+// are emitted with their status if `include_unresolved` is set. Returns the domains which were
+// resolved and do not match a wildcard DNS record.
+#[allow(clippy::too_many_arguments)]
+async fn resolve_domains(
+    target: &str,
+    domains: impl Stream<Item = String>,
+    wildcard_zones: &WildcardZones,
+    certificates: &HashMap<String, Vec<CertificateRecord>>,
+    dns_client: &DnsClient,
+    input_args: &InputArgs,
+    include_unresolved: bool,
+    stage: Stage,
+    events: &Events,
+) -> HashSet<String> {
+    let number_of_parallel_request = input_args.number_of_parallel_requests;
+    let record_types = &input_args.record_types;
 
-    // Build chunks of records in order to avoid having to many opened connections.
-    let futures = domains
-        .into_iter()
-        .filter(|str| parse_domain_name(str).is_ok())
-        .map(|domain| {
-            dns_resolver.lookup_ip(domain).then(|r| {
-                // Display results as soon as they appear
-                future::ready(match r {
-                    Ok(ip) => {
-                        pretty_print(&ip, silent);
-                        Ok(ip)
-                    }
-                    Err(e) => {
-                        // println!("{:?}", e);
-                        Err(e)
-                    }
-                })
-            })
+    // Names which resolved, for skipping the candidates generated again, ex. by a word repeated
+    // after the words remembered by the lists. It only grows with the hits, unlike the set of
+    // every candidate.
+    let hits = Mutex::new(HashSet::new());
+
+    // Bound the number of lookups in flight in order to avoid having to many opened connections.
+    let mut lookups = pin!(domains
+        .filter(|domain| future::ready(parse_domain_name(domain).is_ok()))
+        .filter(|domain| {
+            future::ready(
+                hits.lock()
+                    .map(|hits| !hits.contains(domain))
+                    .unwrap_or(true),
+            )
         })
-        .collect::<Vec<_>>();
-    let stream = futures::stream::iter(futures).buffer_unordered(number_of_parallel_request);
-    result.extend(stream.collect::<Vec<_>>().await);
-    result
-        .iter()
-        .filter(|res| res.is_ok())
-        .map(|res: &Result<LookupIp, _>| res.as_ref().unwrap().clone())
-        .collect::<Vec<LookupIp>>()
+        .map(|domain| async move {
+            let result = dns_client.lookup_ip(domain.as_str()).await;
+            // Query the additional records only for the domains which exist
+            let records = match &result {
+                Ok(lookup) => {
+                    lookup_records(dns_client, &domain, lookup.as_lookup(), record_types).await
+                }
+                Err(_) => (BTreeMap::new(), vec![]),
+            };
+            (domain, result, records)
+        })
+        .buffer_unordered(number_of_parallel_request));
+    let mut resolved = HashSet::new();
+
+    while let Some((domain, result, (records, record_errors))) = lookups.next().await {
+        // The same name may have been in flight more than once
+        if result.is_ok()
+            && !hits
+                .lock()
+                .map(|mut hits| hits.insert(domain.clone()))
+                .unwrap_or(true)
+        {
+            continue;
+        }
+        for e in record_errors {
+            events.warning(ReconError::Resolution {
+                target: target.to_string(),
+                stage,
+                domain: domain.clone(),
+                message: e,
+            });
+        }
+        match result {
+            Ok(lookup) => {
+                let info = domain_info(&lookup, certificates).with_records(records);
+                let matches_wildcard = wildcard_zones.matches(&info.name, &info.ip_addresses);
+                if matches_wildcard
+                    && stage != Stage::Resolve
+                    && input_args.wildcard_dns == WildcardDnsMode::Drop
+                {
+                    continue;
+                }
+                if !matches_wildcard {
+                    resolved.insert(domain.clone());
+                }
+                events.domain_resolved(info.with_wildcard_dns(matches_wildcard).with_target(target))
+            }
+            Err(e) => {
+                let status = LookupStatus::classify(&e);
+                if !status.is_missing_record() {
+                    events.warning(ReconError::Resolution {
+                        target: target.to_string(),
+                        stage,
+                        domain: domain.clone(),
+                        message: e.to_string(),
+                    });
+                }
+                if include_unresolved {
+                    events.domain_unresolved(
+                        DomainInfo::unresolved(domain.clone(), status)
+                            .with_certificates(certificates_for(certificates, &domain))
+                            .with_target(target),
+                    );
+                }
+            }
+        }
+    }
+
+    resolved
 }
 
-fn pretty_print(lookup: &LookupIp, silent: bool) {
+fn domain_info(
+    lookup: &LookupIp,
+    certificates: &HashMap<String, Vec<CertificateRecord>>,
+) -> DomainInfo {
     let records = lookup
         .iter()
         .map(|record| record.to_string())
         .collect::<Vec<String>>();
-    if !silent {
-        println!(
-            "{} {} {}",
-            style(lookup.query().name().to_string()).green(),
-            style(lookup.query().query_type().to_string()).blue().bold(),
-            style(records.join(", ")).magenta().bright()
-        );
-    }
+    let name = lookup.query().name().to_string();
+    let found_in = certificates_for(certificates, &name);
+    DomainInfo::new(name, lookup.query().query_type().to_string(), records)
+        .with_certificates(found_in)
+}
+
+fn is_missing_record(error: &ResolveError) -> bool {
+    LookupStatus::classify(error).is_missing_record()
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
 
+    fn providers(names: &[&str]) -> Vec<Arc<dyn CertificateSource>> {
+        let registry = CertificateSourceRegistry::default();
+        names
+            .iter()
+            .map(|name| registry.get(name).expect("Unknown provider"))
+            .collect()
+    }
+
     #[tokio::test]
     async fn test_validate_config_missing_config() {
-        let config = DomainReconConfig {
-            censys: None,
-            certspotter: None,
-        };
-        let providers = vec![Censys, CertSpotter];
+        let config = DomainReconConfig::default();
+        let providers = providers(&["censys", "certspotter"]);
         let res = validate_config(&Some(config), &providers);
         assert_eq!(false, res.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_validate_config_no_config_file() {
+        assert_eq!(
+            Err(ReconError::MissingConfig),
+            validate_config(&None, &providers(&["censys"]))
+        );
+        assert!(validate_config(&None, &providers(&["certsh"])).is_ok());
+    }
+
     #[tokio::test]
     async fn test_validate_config_censys() {
-        let config = DomainReconConfig {
-            censys: Option::from(vec![CensysConfig {
-                app_id: "".to_string(),
-                secret: "".to_string(),
-            }]),
-            certspotter: None,
-        };
-        let providers = vec![Censys];
+        let config = serde_json::from_value::<DomainReconConfig>(serde_json::json!({
+            "censys": [{"app-id": "84f2fe92", "secret": "bmwjq"}]
+        }))
+        .expect("Invalid config");
+        let providers = providers(&["censys"]);
         let res = validate_config(&Some(config), &providers);
         assert_eq!(true, res.is_ok());
     }
 
     #[tokio::test]
     async fn test_validate_config_certspotter() {
-        let config = DomainReconConfig {
-            censys: None,
-            certspotter: Option::from(vec![CertSpotterConfig {
-                api_key: "".to_string(),
-            }]),
-        };
-        let providers = vec![CertSpotter];
+        let config = serde_json::from_value::<DomainReconConfig>(serde_json::json!({
+            "certspotter": [{"api-key": "k47370_"}]
+        }))
+        .expect("Invalid config");
+        let providers = providers(&["certspotter"]);
         let res = validate_config(&Some(config), &providers);
         assert_eq!(true, res.is_ok());
     }
 
+    #[test]
+    fn test_config_settings() {
+        let config = serde_json::from_value::<DomainReconConfig>(serde_json::json!({
+            "settings": {"certsh": {"base-url": "http://localhost:8080"}},
+            "censys": [{"app-id": "84f2fe92", "secret": "bmwjq"}]
+        }))
+        .expect("Invalid config");
+        assert_eq!(
+            Some("http://localhost:8080"),
+            config
+                .source_settings("certsh")
+                .and_then(|settings| settings.base_url.as_deref())
+        );
+        assert!(config.source_config("censys").is_some());
+        assert!(config.source_config("settings").is_none());
+    }
+
+    #[test]
+    fn test_validate_settings() {
+        let config = serde_json::from_value::<DomainReconConfig>(serde_json::json!({
+            "settings": {"certspotter": {"max-pages": 0}}
+        }))
+        .expect("Invalid config");
+        let no_settings = HashMap::new();
+        assert!(matches!(
+            validate_settings(&config, &providers(&["certspotter"]), &no_settings),
+            Err(ReconError::ProviderConfig { provider, .. }) if provider == "certspotter"
+        ));
+        assert!(validate_settings(&config, &providers(&["certsh"]), &no_settings).is_ok());
+
+        // The input args take precedence over the config file
+        let settings = HashMap::from([(
+            String::from("certspotter"),
+            ProviderSettings {
+                max_pages: Some(2),
+                ..ProviderSettings::default()
+            },
+        )]);
+        assert!(validate_settings(&config, &providers(&["certspotter"]), &settings).is_ok());
+    }
+
     #[tokio::test]
     async fn test_expand_wildcards() {
-        let wildcards = HashSet::from([String::from("*.example.com"), String::from("*.here.com")]);
-        let fqdns = HashSet::from([String::from("example.com"), String::from("there.com")]);
-        let words = HashSet::from([String::from("a"), String::from("b"), String::from("c")]);
+        let wildcards = vec![String::from("*.example.com"), String::from("*.here.com")];
+        let fqdns = HashSet::from([String::from("b.here.com"), String::from("there.com")]);
+        let words = || stream::iter(["a", "b", "c"].map(String::from));
         assert_eq!(
-            HashSet::from([
-                String::from("a.here.com"),
-                String::from("c.example.com"),
-                String::from("b.here.com"),
-                String::from("a.example.com"),
-                String::from("c.here.com"),
-                String::from("b.example.com")
-            ]),
-            expand_wildcards(&wildcards, &fqdns, &words)
+            vec![
+                "a.example.com",
+                "a.here.com",
+                "b.example.com",
+                "c.example.com",
+                "c.here.com"
+            ],
+            expand_wildcards(&wildcards, &fqdns, words())
+                .collect::<Vec<String>>()
                 .await
-                .expect("Error")
         );
+        // Only the candidates within the budget are generated
+        assert_eq!(
+            vec!["a.example.com", "a.here.com", "b.example.com"],
+            expand_wildcards(&wildcards, &fqdns, words())
+                .take(3)
+                .collect::<Vec<String>>()
+                .await
+        );
+    }
+
+    #[test]
+    fn test_certificates_for() {
+        let record = |provider: &str| CertificateRecord {
+            provider: String::from(provider),
+            ..CertificateRecord::default()
+        };
+        let certificates = HashMap::from([
+            (String::from("www.example.com"), vec![record("certsh")]),
+            (
+                String::from("*.example.com"),
+                vec![record("censys"), record("certsh")],
+            ),
+        ]);
+
+        assert_eq!(
+            vec![record("certsh")],
+            certificates_for(&certificates, "www.example.com.")
+        );
+        let expanded = DomainInfo::new(String::from("api.example.com."), String::from("A"), vec![])
+            .with_certificates(certificates_for(&certificates, "api.example.com."));
+        assert_eq!(vec!["censys", "certsh"], expanded.providers);
+        assert!(certificates_for(&certificates, "other.com").is_empty());
+    }
+
+    // DNS over HTTPS client sending every query to the mock server
+    async fn doh_client(
+        server: &wiremock::MockServer,
+        response: wiremock::ResponseTemplate,
+    ) -> DnsClient {
+        wiremock::Mock::given(wiremock::matchers::method("POST"))
+            .respond_with(response)
+            .mount(server)
+            .await;
+        DnsClient::new(
+            None,
+            vec![crate::dns_client::EncryptedEndpoint::Https(format!(
+                "{}/dns-query",
+                server.uri()
+            ))],
+            reqwest::Client::new(),
+            tokio_native_tls::TlsConnector::from(native_tls::TlsConnector::new().expect("Error")),
+            None,
+            10,
+        )
+    }
+
+    async fn collect_domains(
+        domains: &[&str],
+        dns_client: &DnsClient,
+        input_args: &InputArgs,
+    ) -> (HashSet<String>, Vec<ReconEvent>) {
+        let (sender, receiver) = mpsc::unbounded();
+        let events = Events::new(Some(sender), &input_args.targets);
+        let resolved = resolve_domains(
+            "example.com",
+            stream::iter(domains.iter().map(|domain| domain.to_string())),
+            &WildcardZones::default(),
+            &HashMap::new(),
+            dns_client,
+            input_args,
+            input_args.include_unresolved,
+            Stage::Resolve,
+            &events,
+        )
+        .await;
+        drop(events);
+        (resolved, receiver.collect().await)
+    }
+
+    #[tokio::test]
+    async fn test_resolve_duplicate_domains() {
+        use async_std_resolver::proto::op::{Message, MessageType, Query};
+        use async_std_resolver::proto::rr::{rdata::A, Name, RData, Record, RecordType};
+        use std::str::FromStr;
+
+        let name = Name::from_str("www.example.com.").expect("Error");
+        let mut response = Message::new();
+        response
+            .set_message_type(MessageType::Response)
+            .add_query(Query::query(name.clone(), RecordType::A))
+            .add_answer(Record::from_rdata(
+                name,
+                300,
+                RData::A(A::new(192, 0, 2, 1)),
+            ));
+        let server = wiremock::MockServer::start().await;
+        let dns_client = doh_client(
+            &server,
+            wiremock::ResponseTemplate::new(200)
+                .set_body_raw(response.to_vec().expect("Error"), "application/dns-message"),
+        )
+        .await;
+        let input_args = InputArgsBuilder::new(String::from("example.com"))
+            .build()
+            .expect("Error");
+
+        // Ex. a word repeated in a list too large for its repetitions to be skipped
+        let (resolved, events) = collect_domains(
+            &["www.example.com", "www.example.com", "www.example.com"],
+            &dns_client,
+            &input_args,
+        )
+        .await;
+        assert_eq!(HashSet::from([String::from("www.example.com")]), resolved);
+        assert_eq!(
+            1,
+            events
+                .iter()
+                .filter(|event| matches!(event, ReconEvent::DomainResolved(_)))
+                .count()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_unresolved_domains() {
+        let server = wiremock::MockServer::start().await;
+        let dns_client = doh_client(&server, wiremock::ResponseTemplate::new(500)).await;
+        let input_args = InputArgsBuilder::new(String::from("example.com"))
+            .include_unresolved(true)
+            .build()
+            .expect("Error");
+        let (resolved, events) =
+            collect_domains(&["www.example.com"], &dns_client, &input_args).await;
+
+        // Only the resolved domains are expanded or brute forced on the next stages
+        assert!(resolved.is_empty());
+        let mut unresolved = vec![];
+        for event in events {
+            match event {
+                ReconEvent::DomainUnresolved(domain) => unresolved.push(domain),
+                ReconEvent::DomainResolved(domain) => panic!("Unexpected domain {:?}", domain),
+                _ => {}
+            }
+        }
+        assert_eq!(1, unresolved.len());
+        assert_eq!("www.example.com", unresolved[0].name);
+        assert_eq!(LookupStatus::Error, unresolved[0].status);
+    }
+
+    #[derive(Debug)]
+    struct FailingSource {}
+
+    #[async_trait]
+    impl CertificateSource for FailingSource {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        fn config_schema(&self) -> Option<serde_json::Value> {
+            None
+        }
+
+        fn validate(&self, _: Option<&serde_json::Value>) -> anyhow::Result<(), anyhow::Error> {
+            Ok(())
+        }
+
+        async fn fetch(
+            &self,
+            _: &str,
+            _: FetchContext<'_>,
+        ) -> anyhow::Result<FetchResult, anyhow::Error> {
+            Err(anyhow::anyhow!("unavailable"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_stream_events() {
+        let mut registry = CertificateSourceRegistry::empty();
+        registry.register(Arc::new(FailingSource {}));
+        let input_args = InputArgsBuilder::new(String::from("example.com"))
+            .registry(registry)
+            .certificate_providers(&[String::from("failing")])
+            .config(Some(String::from("/does/not/exist.json")))
+            .build()
+            .expect("Error");
+
+        let events = run_stream(input_args).collect::<Vec<_>>().await;
+        assert_eq!(4, events.len());
+        assert!(matches!(
+            &events[0],
+            ReconEvent::StageStarted {
+                target,
+                stage: Stage::Fetch,
+                step: 1,
+                steps: 2
+            } if target == "example.com"
+        ));
+        assert!(matches!(
+            &events[1],
+            ReconEvent::Error(ReconError::ProviderFailed { provider, .. }) if provider == "failing"
+        ));
+        assert!(
+            matches!(&events[2], ReconEvent::NoDomainsFound(target) if target == "example.com")
+        );
+        match &events[3] {
+            ReconEvent::Finished(report) => assert!(report.all_providers_failed()),
+            event => panic!("Unexpected event {:?}", event),
+        }
+    }
+
+    #[test]
+    fn test_brute_force_requires_words() {
+        let builder = || InputArgsBuilder::new(String::from("example.com"));
+        assert!(builder().brute_force(true).build().is_err());
+        assert!(builder().brute_force_subdomains(true).build().is_err());
+        assert!(builder()
+            .brute_force(true)
+            .file(Some(String::from("words.txt")))
+            .build()
+            .is_ok());
+        assert!(builder()
+            .brute_force_subdomains(true)
+            .builtin_words(Some(WordListTier::Small))
+            .build()
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_run_multiple_targets() {
+        let mut registry = CertificateSourceRegistry::empty();
+        registry.register(Arc::new(FailingSource {}));
+        let input_args = InputArgsBuilder::for_targets(vec![
+            String::from("example.com"),
+            String::from(" example.org "),
+            String::from(""),
+            String::from("example.com"),
+        ])
+        .registry(registry)
+        .certificate_providers(&[String::from("failing")])
+        .config(Some(String::from("/does/not/exist.json")))
+        .build()
+        .expect("Error");
+        assert_eq!(vec!["example.com", "example.org"], input_args.targets());
+
+        let report = run(input_args).await.expect("Error");
+        assert_eq!(vec!["example.com", "example.org"], report.targets);
+        let mut failed = report
+            .errors
+            .iter()
+            .filter_map(|error| error.target())
+            .collect::<Vec<_>>();
+        failed.sort();
+        assert_eq!(vec!["example.com", "example.org"], failed);
     }
 }
@@ -0,0 +1,235 @@
+use std::collections::{BTreeSet, HashSet};
+
+/// Words joined to the labels of the resolved domains when no words are given.
+pub const DEFAULT_PERMUTATION_WORDS: &[&str] = &[
+    "dev",
+    "development",
+    "stage",
+    "staging",
+    "stg",
+    "test",
+    "qa",
+    "uat",
+    "preprod",
+    "prod",
+    "production",
+    "sandbox",
+    "demo",
+    "internal",
+    "admin",
+    "api",
+    "app",
+    "beta",
+    "old",
+    "new",
+    "v1",
+    "v2",
+    "backup",
+    "web",
+    "portal",
+];
+
+// Labels which are swapped with each other, ex. `dev-api` becomes `staging-api`
+const ENVIRONMENTS: &[&str] = &[
+    "dev",
+    "development",
+    "stage",
+    "staging",
+    "stg",
+    "test",
+    "qa",
+    "uat",
+    "preprod",
+    "prod",
+    "production",
+    "sandbox",
+];
+
+const MAX_LABEL_LENGTH: usize = 63;
+
+/// Settings of the generator of candidates from the domains which were already resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PermutationSettings {
+    /// Words added as prefixes and suffixes to the first label of the resolved domains.
+    pub words: Vec<String>,
+    /// Maximum number of candidates generated for a target.
+    pub max_candidates: usize,
+}
+
+impl Default for PermutationSettings {
+    fn default() -> PermutationSettings {
+        PermutationSettings {
+            words: DEFAULT_PERMUTATION_WORDS
+                .iter()
+                .map(|word| word.to_string())
+                .collect(),
+            max_candidates: 10_000,
+        }
+    }
+}
+
+impl PermutationSettings {
+    /// Generate the permutations of the first label of every resolved domain, ex. `dev-api`,
+    /// `api-dev`, `devapi`, `dev.api`, `api2` or `staging-api` for `api` and `dev-api`. Domains
+    /// already known are left out. The domains are processed in order, so that the candidates kept
+    /// under the limit are always the same.
+    pub(crate) fn permutations(&self, resolved: &HashSet<String>) -> HashSet<String> {
+        let words = self
+            .words
+            .iter()
+            .map(|word| word.trim().to_lowercase())
+            .filter(|word| !word.is_empty())
+            .collect::<BTreeSet<String>>();
+        let mut candidates = HashSet::new();
+        let sorted = resolved
+            .iter()
+            .map(|domain| domain.trim_end_matches('.').to_lowercase())
+            .collect::<BTreeSet<String>>();
+        for domain in &sorted {
+            let Some((label, parent)) = domain.split_once('.') else {
+                continue;
+            };
+            for variant in label_variants(label, &words) {
+                if candidates.len() >= self.max_candidates {
+                    return candidates;
+                }
+                let candidate = format!("{}.{}", variant, parent);
+                if !sorted.contains(&candidate) && is_valid(&variant) {
+                    candidates.insert(candidate);
+                }
+            }
+        }
+        candidates
+    }
+}
+
+fn label_variants(label: &str, words: &BTreeSet<String>) -> Vec<String> {
+    let mut variants = vec![];
+
+    // Prefix and suffix words, with and without a hyphen, or as a new level
+    for word in words {
+        variants.push(format!("{}-{}", word, label));
+        variants.push(format!("{}-{}", label, word));
+        variants.push(format!("{}{}", word, label));
+        variants.push(format!("{}{}", label, word));
+        variants.push(format!("{}.{}", word, label));
+    }
+
+    variants.extend(number_variants(label));
+
+    // Swap every environment found among the hyphen separated parts of the label
+    let parts = label.split('-').collect::<Vec<&str>>();
+    for (i, part) in parts.iter().enumerate() {
+        if ENVIRONMENTS.contains(part) {
+            for environment in ENVIRONMENTS
+                .iter()
+                .filter(|environment| *environment != part)
+            {
+                let mut swapped = parts.clone();
+                swapped[i] = environment;
+                variants.push(swapped.join("-"));
+            }
+        }
+    }
+
+    variants
+}
+
+// Increment and decrement the number at the end of the label, keeping its width, ex. `api01`
+// gives `api00` and `api02`. Labels without a number get one.
+fn number_variants(label: &str) -> Vec<String> {
+    let stem = label.trim_end_matches(|c: char| c.is_ascii_digit());
+    let digits = &label[stem.len()..];
+    if digits.is_empty() {
+        return vec![format!("{}1", label), format!("{}2", label)];
+    }
+    let Ok(number) = digits.parse::<u64>() else {
+        return vec![];
+    };
+    let width = digits.len();
+    let mut variants = vec![];
+    // The largest number has no next one
+    if let Some(next) = number.checked_add(1) {
+        variants.push(format!("{}{:0width$}", stem, next, width = width));
+    }
+    if number > 0 {
+        variants.push(format!("{}{:0width$}", stem, number - 1, width = width));
+    }
+    variants
+}
+
+fn is_valid(label: &str) -> bool {
+    label.split('.').all(|part| {
+        !part.is_empty()
+            && part.len() <= MAX_LABEL_LENGTH
+            && !part.starts_with('-')
+            && !part.ends_with('-')
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(words: &[&str], max_candidates: usize) -> PermutationSettings {
+        PermutationSettings {
+            words: words.iter().map(|word| word.to_string()).collect(),
+            max_candidates,
+        }
+    }
+
+    #[test]
+    fn test_number_variants() {
+        assert_eq!(vec!["api1", "api2"], number_variants("api"));
+        assert_eq!(vec!["api02", "api00"], number_variants("api01"));
+        assert_eq!(vec!["node1"], number_variants("node0"));
+        assert_eq!(
+            vec!["node18446744073709551614"],
+            number_variants("node18446744073709551615")
+        );
+    }
+
+    #[test]
+    fn test_permutations() {
+        let resolved = HashSet::from([
+            String::from("api.example.com."),
+            String::from("dev-web.example.com"),
+            String::from("api-dev.example.com"),
+        ]);
+        let candidates = settings(&["dev"], 1000).permutations(&resolved);
+
+        for expected in [
+            "dev-api.example.com",
+            "devapi.example.com",
+            "apidev.example.com",
+            "dev.api.example.com",
+            "api2.example.com",
+            "staging-web.example.com",
+            "prod-web.example.com",
+        ] {
+            assert!(candidates.contains(expected), "Missing {}", expected);
+        }
+        // Already resolved
+        assert!(!candidates.contains("api-dev.example.com"));
+        assert!(!candidates.contains("dev-web.example.com"));
+    }
+
+    #[test]
+    fn test_permutations_limit() {
+        let resolved = HashSet::from([String::from("api.example.com")]);
+        let first = settings(&["dev", "test"], 5).permutations(&resolved);
+        assert_eq!(5, first.len());
+        assert_eq!(first, settings(&["dev", "test"], 5).permutations(&resolved));
+    }
+
+    #[test]
+    fn test_is_valid() {
+        assert!(is_valid("dev.api-2"));
+        assert!(!is_valid("-api"));
+        assert!(!is_valid(&"a".repeat(64)));
+        assert!(!is_valid("api..dev"));
+    }
+}
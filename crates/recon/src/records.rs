@@ -0,0 +1,167 @@
+use crate::dns_client::DnsClient;
+use async_std_resolver::lookup::Lookup;
+use async_std_resolver::proto::rr::RecordType;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub struct UnknownRecordType {
+    record_type: String,
+}
+
+impl Display for UnknownRecordType {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> anyhow::Result<(), std::fmt::Error> {
+        write!(fmt, "Unknown DNS record type: \"{}\"!", self.record_type)
+    }
+}
+
+impl std::error::Error for UnknownRecordType {}
+
+/// DNS record queried for every resolved domain in addition to its IP addresses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum DnsRecordType {
+    /// Every canonical name on the way from the domain to its addresses.
+    Cname,
+    Mx,
+    Ns,
+    Txt,
+    Caa,
+    Soa,
+}
+
+impl DnsRecordType {
+    fn record_type(&self) -> RecordType {
+        match self {
+            DnsRecordType::Cname => RecordType::CNAME,
+            DnsRecordType::Mx => RecordType::MX,
+            DnsRecordType::Ns => RecordType::NS,
+            DnsRecordType::Txt => RecordType::TXT,
+            DnsRecordType::Caa => RecordType::CAA,
+            DnsRecordType::Soa => RecordType::SOA,
+        }
+    }
+}
+
+impl Display for DnsRecordType {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> anyhow::Result<(), std::fmt::Error> {
+        write!(fmt, "{}", self.record_type())
+    }
+}
+
+impl FromStr for DnsRecordType {
+    type Err = UnknownRecordType;
+
+    fn from_str(input: &str) -> anyhow::Result<DnsRecordType, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "cname" => Ok(DnsRecordType::Cname),
+            "mx" => Ok(DnsRecordType::Mx),
+            "ns" => Ok(DnsRecordType::Ns),
+            "txt" => Ok(DnsRecordType::Txt),
+            "caa" => Ok(DnsRecordType::Caa),
+            "soa" => Ok(DnsRecordType::Soa),
+            _ => Err(UnknownRecordType {
+                record_type: input.to_string(),
+            }),
+        }
+    }
+}
+
+/// Records of the given type contained by a lookup.
+fn records_of(lookup: &Lookup, record_type: RecordType) -> Vec<String> {
+    lookup
+        .record_iter()
+        .filter(|record| record.record_type() == record_type)
+        .filter_map(|record| record.data())
+        .map(|data| data.to_string())
+        .collect()
+}
+
+/// Query the records of the given types for `domain`. The CNAME chain is taken from the lookup of
+/// the IP addresses, which already followed it. Returns the records found together with the
+/// failed queries. Missing records are not failures.
+pub(crate) async fn lookup_records(
+    dns_client: &DnsClient,
+    domain: &str,
+    ip_lookup: &Lookup,
+    record_types: &[DnsRecordType],
+) -> (BTreeMap<DnsRecordType, Vec<String>>, Vec<String>) {
+    let mut records = BTreeMap::new();
+    let mut errors = vec![];
+    for record_type in record_types {
+        let found = match record_type {
+            DnsRecordType::Cname => records_of(ip_lookup, RecordType::CNAME),
+            _ => match dns_client.lookup(domain, record_type.record_type()).await {
+                Ok(lookup) => records_of(&lookup, record_type.record_type()),
+                Err(e) if crate::is_missing_record(&e) => vec![],
+                Err(e) => {
+                    errors.push(format!("{} lookup failed: {}", record_type, e));
+                    vec![]
+                }
+            },
+        };
+        if !found.is_empty() {
+            records.insert(*record_type, found);
+        }
+    }
+    (records, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_std_resolver::proto::op::Query;
+    use async_std_resolver::proto::rr::rdata::{A, CNAME};
+    use async_std_resolver::proto::rr::{Name, RData, Record};
+    use std::sync::Arc;
+
+    fn name(name: &str) -> Name {
+        Name::from_str(name).expect("Invalid name")
+    }
+
+    #[test]
+    fn test_record_type_from_str() {
+        assert_eq!(
+            DnsRecordType::Cname,
+            DnsRecordType::from_str("CNAME").expect("Error")
+        );
+        assert_eq!(
+            DnsRecordType::Mx,
+            DnsRecordType::from_str("mx").expect("Error")
+        );
+        assert!(DnsRecordType::from_str("aaaaa").is_err());
+    }
+
+    #[test]
+    fn test_records_of_cname_chain() {
+        let records: Arc<[Record]> = Arc::from(vec![
+            Record::from_rdata(
+                name("www.example.com."),
+                300,
+                RData::CNAME(CNAME(name("cdn.example.net."))),
+            ),
+            Record::from_rdata(
+                name("cdn.example.net."),
+                300,
+                RData::CNAME(CNAME(name("edge.example.org."))),
+            ),
+            Record::from_rdata(
+                name("edge.example.org."),
+                300,
+                RData::A(A::new(192, 0, 2, 1)),
+            ),
+        ]);
+        let lookup = Lookup::new_with_max_ttl(
+            Query::query(name("www.example.com."), RecordType::A),
+            records,
+        );
+
+        assert_eq!(
+            vec!["cdn.example.net.", "edge.example.org."],
+            records_of(&lookup, RecordType::CNAME)
+        );
+        assert_eq!(vec!["192.0.2.1"], records_of(&lookup, RecordType::A));
+    }
+}
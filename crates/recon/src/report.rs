@@ -0,0 +1,259 @@
+use crate::scope::OutOfScope;
+use crate::DomainInfo;
+use serde::Serialize;
+use std::fmt::Display;
+use std::time::{Duration, SystemTime};
+
+/// Stage of a reconnaissance run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Reading and validating the configuration, building the HTTP client and the DNS resolver.
+    Setup,
+    /// Fetching certificates from the certificate providers.
+    Fetch,
+    /// Resolving the names found in the certificates.
+    Resolve,
+    /// Expanding the wildcards with the words of the word list and resolving the results.
+    Expand,
+    /// Resolving the permutations of the domains which were resolved.
+    Permute,
+}
+
+impl Display for Stage {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> anyhow::Result<(), std::fmt::Error> {
+        let stage = match self {
+            Stage::Setup => "setup",
+            Stage::Fetch => "fetch",
+            Stage::Resolve => "resolve",
+            Stage::Expand => "expand",
+            Stage::Permute => "permute",
+        };
+        write!(fmt, "{}", stage)
+    }
+}
+
+/// Errors of a reconnaissance run. Returned by `run` when the run can not continue, or reported in
+/// the `ReconReport` when only a part of the run failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReconError {
+    /// The config file could not be read or parsed.
+    Config(String),
+    /// The config file was not found, but a provider requires one.
+    MissingConfig,
+    /// The section of a certificate provider in the config file is missing or invalid.
+    ProviderConfig { provider: String, message: String },
+    /// The HTTP client could not be built from the given settings.
+    HttpClient(String),
+    /// The DNS resolver could not be built.
+    DnsResolver(String),
+    /// The word list could not be read.
+    WordList { path: String, message: String },
+    /// A certificate provider failed without returning any names for the target.
+    ProviderFailed {
+        target: String,
+        provider: String,
+        message: String,
+    },
+    /// A certificate provider returned only part of the results for the target, ex. a page could
+    /// not be fetched.
+    ProviderIncomplete {
+        target: String,
+        provider: String,
+        message: String,
+    },
+    /// A name could not be resolved for a reason other than a missing record, ex. a timeout.
+    Resolution {
+        target: String,
+        stage: Stage,
+        domain: String,
+        message: String,
+    },
+}
+
+impl ReconError {
+    /// Stage of the run in which the error happened.
+    pub fn stage(&self) -> Stage {
+        match self {
+            ReconError::Config(_)
+            | ReconError::MissingConfig
+            | ReconError::ProviderConfig { .. }
+            | ReconError::HttpClient(_)
+            | ReconError::DnsResolver(_) => Stage::Setup,
+            ReconError::WordList { .. } => Stage::Expand,
+            ReconError::ProviderFailed { .. } | ReconError::ProviderIncomplete { .. } => {
+                Stage::Fetch
+            }
+            ReconError::Resolution { stage, .. } => *stage,
+        }
+    }
+
+    /// Name of the certificate provider the error belongs to, if any.
+    pub fn provider(&self) -> Option<&str> {
+        match self {
+            ReconError::ProviderConfig { provider, .. }
+            | ReconError::ProviderFailed { provider, .. }
+            | ReconError::ProviderIncomplete { provider, .. } => Some(provider),
+            _ => None,
+        }
+    }
+
+    /// Target the error belongs to, if it does not concern the whole run.
+    pub fn target(&self) -> Option<&str> {
+        match self {
+            ReconError::ProviderFailed { target, .. }
+            | ReconError::ProviderIncomplete { target, .. }
+            | ReconError::Resolution { target, .. } => Some(target),
+            _ => None,
+        }
+    }
+}
+
+impl Display for ReconError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> anyhow::Result<(), std::fmt::Error> {
+        match self {
+            ReconError::Config(message) => write!(fmt, "Invalid config file: {}", message),
+            ReconError::MissingConfig => write!(fmt, "No config file provided!"),
+            ReconError::ProviderConfig { provider, message } => write!(
+                fmt,
+                "Invalid configuration of provider {}: {}",
+                provider, message
+            ),
+            ReconError::HttpClient(message) => write!(fmt, "{}", message),
+            ReconError::DnsResolver(message) => {
+                write!(fmt, "Could not build the DNS resolver: {}", message)
+            }
+            ReconError::WordList { path, message } => {
+                write!(fmt, "Could not read word list \"{}\": {}", path, message)
+            }
+            ReconError::ProviderFailed {
+                target,
+                provider,
+                message,
+            } => write!(
+                fmt,
+                "Could not fetch {} from provider {}. Error: {}",
+                target, provider, message
+            ),
+            ReconError::ProviderIncomplete {
+                target,
+                provider,
+                message,
+            } => write!(
+                fmt,
+                "Could not fetch all results for {} from provider {}. Error: {}",
+                target, provider, message
+            ),
+            ReconError::Resolution {
+                domain, message, ..
+            } => write!(fmt, "Could not resolve \"{}\": {}", domain, message),
+        }
+    }
+}
+
+impl std::error::Error for ReconError {}
+
+/// Number of names returned by a certificate provider which did not fail.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProviderSummary {
+    pub target: String,
+    pub provider: String,
+    pub names: usize,
+    pub pages: usize,
+}
+
+/// Outcome of a reconnaissance run: the resolvable domains together with the failures which did
+/// not stop the run.
+#[derive(Debug, Clone, Default)]
+pub struct ReconReport {
+    /// Domains which were scanned.
+    pub targets: Vec<String>,
+    /// Time at which the run started.
+    pub started_at: Option<SystemTime>,
+    /// Time it took to complete the run.
+    pub duration: Duration,
+    /// Domains which were resolved, and the ones which could not be resolved if they are included.
+    /// Filled only by `run`, `run_stream` emits them one by one instead.
+    pub domains: Vec<DomainInfo>,
+    /// Certificate providers which returned results.
+    pub providers: Vec<ProviderSummary>,
+    /// Names from the certificates which were left out by the scope, for every target.
+    pub out_of_scope: Vec<OutOfScope>,
+    /// Failures which left out part of the results, ex. a failing certificate provider.
+    pub errors: Vec<ReconError>,
+    /// Failures which are unlikely to affect the results, ex. a timed out DNS lookup.
+    pub warnings: Vec<ReconError>,
+}
+
+impl ReconReport {
+    /// True if every certificate provider failed, so no domains could be found.
+    pub fn all_providers_failed(&self) -> bool {
+        self.providers.is_empty()
+            && self
+                .errors
+                .iter()
+                .any(|error| matches!(error, ReconError::ProviderFailed { .. }))
+    }
+
+    /// Errors and warnings reported for the given certificate provider.
+    pub fn issues_for_provider<'a>(
+        &'a self,
+        provider: &'a str,
+    ) -> impl Iterator<Item = &'a ReconError> {
+        self.issues()
+            .filter(move |error| error.provider() == Some(provider))
+    }
+
+    /// Errors and warnings reported in the given stage of the run.
+    pub fn issues_in_stage(&self, stage: Stage) -> impl Iterator<Item = &ReconError> {
+        self.issues().filter(move |error| error.stage() == stage)
+    }
+
+    fn issues(&self) -> impl Iterator<Item = &ReconError> {
+        self.errors.iter().chain(self.warnings.iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_issues() {
+        let report = ReconReport {
+            providers: vec![],
+            errors: vec![ReconError::ProviderFailed {
+                target: String::from("example.com"),
+                provider: String::from("censys"),
+                message: String::from("HTTP 500"),
+            }],
+            warnings: vec![ReconError::Resolution {
+                target: String::from("example.com"),
+                stage: Stage::Resolve,
+                domain: String::from("www.example.com"),
+                message: String::from("timed out"),
+            }],
+            ..ReconReport::default()
+        };
+
+        assert!(report.all_providers_failed());
+        assert_eq!(Some("example.com"), report.errors[0].target());
+        assert_eq!(Some("example.com"), report.warnings[0].target());
+        assert_eq!(1, report.issues_for_provider("censys").count());
+        assert_eq!(0, report.issues_for_provider("certsh").count());
+        assert_eq!(1, report.issues_in_stage(Stage::Fetch).count());
+        assert_eq!(1, report.issues_in_stage(Stage::Resolve).count());
+        assert_eq!(0, report.issues_in_stage(Stage::Expand).count());
+    }
+
+    #[test]
+    fn test_display_provider_config() {
+        let error = ReconError::ProviderConfig {
+            provider: String::from("censys"),
+            message: String::from("No credentials!"),
+        };
+        assert_eq!(
+            "Invalid configuration of provider censys: No credentials!",
+            error.to_string()
+        );
+    }
+}
@@ -1,5 +1,14 @@
+use crate::dns_client::EncryptedEndpoint;
+use anyhow::anyhow;
+use async_std_resolver::config::Protocol;
+use reqwest::Url;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
 use std::str::FromStr;
 
+const DNS_PORT: u16 = 53;
+const DNS_OVER_TLS_PORT: u16 = 853;
+
 #[derive(Debug)]
 #[allow(dead_code, unused_variables)]
 pub(crate) struct UnknownDNSResolver {
@@ -14,26 +23,220 @@ impl UnknownDNSResolver {
 
 impl std::fmt::Display for UnknownDNSResolver {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> anyhow::Result<(), std::fmt::Error> {
-        write!(fmt, "Unknown DNS resolver!")
+        write!(fmt, "Unknown DNS resolver: \"{}\"!", self.resolver_name)
     }
 }
 
-#[derive(Debug)]
+/// Transport protocol used for querying the nameservers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DnsProtocol {
+    #[default]
+    Udp,
+    Tcp,
+}
+
+impl DnsProtocol {
+    pub(crate) fn protocol(&self) -> Protocol {
+        match self {
+            DnsProtocol::Udp => Protocol::Udp,
+            DnsProtocol::Tcp => Protocol::Tcp,
+        }
+    }
+}
+
+impl FromStr for DnsProtocol {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> anyhow::Result<DnsProtocol, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "udp" => Ok(DnsProtocol::Udp),
+            "tcp" => Ok(DnsProtocol::Tcp),
+            _ => Err(anyhow!("Unknown DNS protocol: \"{}\"!", input)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub(crate) enum DNSResolver {
     Google,
     CloudFlare,
     Quad9,
+    /// Nameserver given by its address, ex. `10.0.0.2`, `10.0.0.2:5353` or
+    /// `tcp://[2001:db8::1]:53`. Without a protocol, the protocol selected for every resolver is
+    /// used.
+    Custom {
+        address: SocketAddr,
+        protocol: Option<DnsProtocol>,
+    },
+    /// File with a nameserver address on every line.
+    File(String),
+    /// DNS over HTTPS or DNS over TLS resolver, ex. `google-doh`, `cloudflare-dot`,
+    /// `https://dns.example.com/dns-query` or `tls://dns.example.com:853`.
+    Encrypted(EncryptedEndpoint),
+}
+
+impl DNSResolver {
+    fn preset(input: &str) -> Option<DNSResolver> {
+        let https = |url: &str| DNSResolver::Encrypted(EncryptedEndpoint::Https(url.to_string()));
+        let tls = |host: &str| {
+            DNSResolver::Encrypted(EncryptedEndpoint::Tls {
+                host: host.to_string(),
+                port: DNS_OVER_TLS_PORT,
+            })
+        };
+        match input {
+            "google" => Some(DNSResolver::Google),
+            "cloudflare" => Some(DNSResolver::CloudFlare),
+            "quad9" => Some(DNSResolver::Quad9),
+            "google-doh" => Some(https("https://dns.google/dns-query")),
+            "cloudflare-doh" => Some(https("https://cloudflare-dns.com/dns-query")),
+            "quad9-doh" => Some(https("https://dns.quad9.net/dns-query")),
+            "google-dot" => Some(tls("dns.google")),
+            "cloudflare-dot" => Some(tls("one.one.one.one")),
+            "quad9-dot" => Some(tls("dns.quad9.net")),
+            _ => None,
+        }
+    }
+
+    // Parse the URL of a DNS over HTTPS or a DNS over TLS resolver
+    fn parse_encrypted(input: &str) -> Option<DNSResolver> {
+        let url = Url::parse(input).ok()?;
+        let endpoint = match url.scheme() {
+            "https" => EncryptedEndpoint::Https(input.to_string()),
+            "tls" => EncryptedEndpoint::Tls {
+                host: url
+                    .host_str()?
+                    .trim_start_matches('[')
+                    .trim_end_matches(']')
+                    .to_string(),
+                port: url.port().unwrap_or(DNS_OVER_TLS_PORT),
+            },
+            _ => return None,
+        };
+        Some(DNSResolver::Encrypted(endpoint))
+    }
+
+    // Parse the address of a nameserver, optionally prefixed with its protocol
+    fn parse_custom(input: &str) -> Option<DNSResolver> {
+        let (protocol, address) = match input.split_once("://") {
+            Some((protocol, address)) => (Some(DnsProtocol::from_str(protocol).ok()?), address),
+            None => (None, input),
+        };
+        let address = SocketAddr::from_str(address).ok().or_else(|| {
+            IpAddr::from_str(address)
+                .ok()
+                .map(|ip| SocketAddr::new(ip, DNS_PORT))
+        })?;
+        Some(DNSResolver::Custom { address, protocol })
+    }
+
+    /// Parse the nameservers listed in a resolver file. Empty lines and lines starting with `#` are
+    /// skipped.
+    pub(crate) fn parse_file(contents: &str) -> anyhow::Result<Vec<DNSResolver>, anyhow::Error> {
+        contents
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                DNSResolver::parse_custom(line)
+                    .or_else(|| DNSResolver::parse_encrypted(line))
+                    .ok_or_else(|| anyhow!("Invalid nameserver address: \"{}\"!", line))
+            })
+            .collect()
+    }
 }
 
 impl FromStr for DNSResolver {
     type Err = UnknownDNSResolver;
 
     fn from_str(input: &str) -> anyhow::Result<DNSResolver, Self::Err> {
-        match input {
-            "google" => Ok(DNSResolver::Google),
-            "cloudflare" => Ok(DNSResolver::CloudFlare),
-            "quad9" => Ok(DNSResolver::Quad9),
-            _ => return Err(UnknownDNSResolver::new(input.to_string())),
+        DNSResolver::preset(input)
+            .or_else(|| DNSResolver::parse_custom(input))
+            .or_else(|| DNSResolver::parse_encrypted(input))
+            .or_else(|| {
+                Path::new(input)
+                    .is_file()
+                    .then(|| DNSResolver::File(input.to_string()))
+            })
+            .ok_or_else(|| UnknownDNSResolver::new(input.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn custom(address: &str, protocol: Option<DnsProtocol>) -> DNSResolver {
+        DNSResolver::Custom {
+            address: SocketAddr::from_str(address).expect("Invalid address"),
+            protocol,
         }
     }
+
+    #[test]
+    fn test_from_str_custom() {
+        assert_eq!(
+            custom("10.0.0.2:53", None),
+            DNSResolver::from_str("10.0.0.2").expect("Error")
+        );
+        assert_eq!(
+            custom("10.0.0.2:5353", None),
+            DNSResolver::from_str("10.0.0.2:5353").expect("Error")
+        );
+        assert_eq!(
+            custom("[2001:db8::1]:53", Some(DnsProtocol::Tcp)),
+            DNSResolver::from_str("tcp://2001:db8::1").expect("Error")
+        );
+        assert_eq!(
+            custom("[2001:db8::1]:5353", Some(DnsProtocol::Udp)),
+            DNSResolver::from_str("udp://[2001:db8::1]:5353").expect("Error")
+        );
+        assert!(DNSResolver::from_str("quic://10.0.0.2").is_err());
+        assert!(DNSResolver::from_str("/does/not/exist.txt").is_err());
+    }
+
+    #[test]
+    fn test_from_str_encrypted() {
+        assert_eq!(
+            DNSResolver::Encrypted(EncryptedEndpoint::Https(
+                "https://dns.google/dns-query".to_string()
+            )),
+            DNSResolver::from_str("google-doh").expect("Error")
+        );
+        assert_eq!(
+            DNSResolver::Encrypted(EncryptedEndpoint::Https(
+                "https://doh.example.com/dns-query".to_string()
+            )),
+            DNSResolver::from_str("https://doh.example.com/dns-query").expect("Error")
+        );
+        assert_eq!(
+            DNSResolver::Encrypted(EncryptedEndpoint::Tls {
+                host: "dot.example.com".to_string(),
+                port: 853
+            }),
+            DNSResolver::from_str("tls://dot.example.com").expect("Error")
+        );
+        assert_eq!(
+            DNSResolver::Encrypted(EncryptedEndpoint::Tls {
+                host: "2001:db8::1".to_string(),
+                port: 8853
+            }),
+            DNSResolver::from_str("tls://[2001:db8::1]:8853").expect("Error")
+        );
+        assert!(DNSResolver::from_str("http://doh.example.com/dns-query").is_err());
+    }
+
+    #[test]
+    fn test_parse_file() {
+        let resolvers = DNSResolver::parse_file("# internal\n10.0.0.2\n\n tcp://10.0.0.3:5353 \n")
+            .expect("Error");
+        assert_eq!(
+            vec![
+                custom("10.0.0.2:53", None),
+                custom("10.0.0.3:5353", Some(DnsProtocol::Tcp))
+            ],
+            resolvers
+        );
+        assert!(DNSResolver::parse_file("10.0.0.2\nnot-an-ip\n").is_err());
+    }
 }
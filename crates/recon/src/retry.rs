@@ -0,0 +1,201 @@
+use rand::Rng;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Retry policy applied to the HTTP requests sent to a certificate provider. Failed connections,
+/// timeouts and HTTP 429, 500, 502, 503 and 504 responses are retried with exponential backoff and
+/// jitter. The `Retry-After` header is honoured when present.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts for a request, including the first one.
+    pub max_attempts: usize,
+    /// Backoff before the first retry in milliseconds. Doubled for every following retry.
+    pub initial_backoff_ms: u64,
+    /// Upper bound for the backoff and for the `Retry-After` wait in milliseconds.
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 30_000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy which sends every request exactly once.
+    pub fn none() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 1,
+            ..RetryPolicy::default()
+        }
+    }
+
+    // Backoff before retrying after the given number of failed attempts. The jitter picks a random
+    // duration from the upper half of the exponential backoff.
+    fn backoff(&self, attempt: usize) -> Duration {
+        let exponent = u32::try_from(attempt.saturating_sub(1)).unwrap_or(u32::MAX);
+        let backoff = self
+            .initial_backoff_ms
+            .saturating_mul(2u64.saturating_pow(exponent))
+            .min(self.max_backoff_ms);
+        Duration::from_millis(rand::thread_rng().gen_range(backoff / 2..=backoff))
+    }
+
+    fn max_backoff(&self) -> Duration {
+        Duration::from_millis(self.max_backoff_ms)
+    }
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+// Time to wait requested by the server in the `Retry-After` header, if given in seconds
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+/// Send `request` retrying it according to `policy`. Rate limited responses are only retried if
+/// `retry_rate_limited` is set, otherwise they are returned right away, ex. for failing over to
+/// another credential. The last response or error is returned once the attempts run out.
+pub(crate) async fn send(
+    policy: &RetryPolicy,
+    mut request: RequestBuilder,
+    retry_rate_limited: bool,
+) -> reqwest::Result<Response> {
+    let mut attempt = 1;
+    loop {
+        // Requests with streaming bodies can not be cloned, these are sent only once
+        let next = request.try_clone();
+        let result = request.send().await;
+
+        let retryable = match &result {
+            Ok(response) => {
+                is_retryable_status(response.status())
+                    && (retry_rate_limited || response.status() != StatusCode::TOO_MANY_REQUESTS)
+            }
+            Err(e) => e.is_connect() || e.is_timeout(),
+        };
+        let next = match next {
+            Some(next) if retryable && attempt < policy.max_attempts => next,
+            _ => return result,
+        };
+
+        let wait = result
+            .as_ref()
+            .ok()
+            .and_then(retry_after)
+            .map(|wait| wait.min(policy.max_backoff()))
+            .unwrap_or_else(|| policy.backoff(attempt));
+        tokio::time::sleep(wait).await;
+        request = next;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn fast_policy(max_attempts: usize) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff_ms: 1,
+            max_backoff_ms: 5,
+        }
+    }
+
+    async fn mock_failures(server: &MockServer, status: u16, times: u64) {
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(status))
+            .up_to_n_times(times)
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_send_retries_server_errors() {
+        let server = MockServer::start().await;
+        mock_failures(&server, 503, 2).await;
+
+        let client = reqwest::Client::new();
+        let response = send(&fast_policy(3), client.get(server.uri()), true)
+            .await
+            .expect("Error");
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[tokio::test]
+    async fn test_send_gives_up_after_max_attempts() {
+        let server = MockServer::start().await;
+        mock_failures(&server, 502, 2).await;
+
+        let client = reqwest::Client::new();
+        let response = send(&fast_policy(2), client.get(server.uri()), true)
+            .await
+            .expect("Error");
+        assert_eq!(StatusCode::BAD_GATEWAY, response.status());
+    }
+
+    #[tokio::test]
+    async fn test_send_does_not_retry_client_errors() {
+        let server = MockServer::start().await;
+        mock_failures(&server, 404, 1).await;
+
+        let client = reqwest::Client::new();
+        let response = send(&fast_policy(3), client.get(server.uri()), true)
+            .await
+            .expect("Error");
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+    }
+
+    #[tokio::test]
+    async fn test_send_rate_limited_without_retry() {
+        let server = MockServer::start().await;
+        mock_failures(&server, 429, 1).await;
+
+        let client = reqwest::Client::new();
+        let response = send(&fast_policy(3), client.get(server.uri()), false)
+            .await
+            .expect("Error");
+        assert_eq!(StatusCode::TOO_MANY_REQUESTS, response.status());
+    }
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 1_000,
+        };
+        assert!(policy.backoff(1) <= Duration::from_millis(100));
+        assert!(policy.backoff(1) >= Duration::from_millis(50));
+        assert!(policy.backoff(9) <= Duration::from_millis(1_000));
+        assert!(policy.backoff(9) >= Duration::from_millis(500));
+    }
+}
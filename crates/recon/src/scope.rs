@@ -0,0 +1,155 @@
+use addr::parse_domain_name;
+use serde::Serialize;
+
+/// Names found in the certificates of a target which were left out by the scope.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct OutOfScope {
+    pub target: String,
+    pub names: Vec<String>,
+}
+
+/// Rules deciding which names of a target are resolved, from its certificates or generated.
+/// Patterns are matched case-insensitively, `*` matching any run of characters, ex.
+/// `*.example.net`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Scope {
+    /// Keep only the names under the registrable domain of the target, besides the included ones.
+    pub(crate) strict: bool,
+    pub(crate) include: Vec<String>,
+    pub(crate) exclude: Vec<String>,
+}
+
+impl Default for Scope {
+    fn default() -> Scope {
+        Scope {
+            strict: true,
+            include: vec![],
+            exclude: vec![],
+        }
+    }
+}
+
+impl Scope {
+    pub(crate) fn new(strict: bool, include: &[String], exclude: &[String]) -> Scope {
+        let normalize = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|pattern| pattern.trim().to_lowercase())
+                .filter(|pattern| !pattern.is_empty())
+                .collect()
+        };
+        Scope {
+            strict,
+            include: normalize(include),
+            exclude: normalize(exclude),
+        }
+    }
+
+    /// Whether `name` found in the certificates of `target`, or generated for it, is in scope.
+    /// Wildcard names are judged by the domain they cover, ex. `example.com` for `*.example.com`,
+    /// but are also excluded by the patterns matching them as they are.
+    pub(crate) fn contains(&self, target: &str, name: &str) -> bool {
+        let wildcard = name.to_lowercase();
+        let name = wildcard.trim_start_matches("*.");
+        if self
+            .exclude
+            .iter()
+            .any(|pattern| glob_matches(pattern, name) || glob_matches(pattern, &wildcard))
+        {
+            return false;
+        }
+        !self.strict
+            || is_under(name, registrable_domain(target))
+            || self
+                .include
+                .iter()
+                .any(|pattern| glob_matches(pattern, name))
+    }
+}
+
+// The target itself is used if it has no registrable domain, ex. an unknown suffix
+fn registrable_domain(target: &str) -> &str {
+    parse_domain_name(target)
+        .ok()
+        .and_then(|domain| domain.root())
+        .unwrap_or(target)
+}
+
+fn is_under(name: &str, domain: &str) -> bool {
+    let domain = domain.to_lowercase();
+    name == domain || name.ends_with(&format!(".{}", domain))
+}
+
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.as_bytes();
+    let name = name.as_bytes();
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` in the pattern and of the name where it started matching
+    let mut backtrack: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && pattern[p] == b'*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == name[n] {
+            p += 1;
+            n += 1;
+        } else if let Some((star, matched)) = backtrack {
+            // Let the last `*` match one more character
+            p = star + 1;
+            n = matched + 1;
+            backtrack = Some((star, n));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(patterns: &[&str]) -> Vec<String> {
+        patterns.iter().map(|pattern| pattern.to_string()).collect()
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("*.example.com", "www.example.com"));
+        assert!(glob_matches("*.example.com", "a.b.example.com"));
+        assert!(!glob_matches("*.example.com", "example.com"));
+        assert!(glob_matches("api-*.example.com", "api-v2.example.com"));
+        assert!(glob_matches("*", "anything"));
+        assert!(!glob_matches("www.example.com", "www.example.co"));
+    }
+
+    #[test]
+    fn test_strict_scope() {
+        let scope = Scope::default();
+        assert!(scope.contains("www.example.co.uk", "example.co.uk"));
+        assert!(scope.contains("www.example.co.uk", "*.api.example.co.uk"));
+        assert!(scope.contains("example.com", "WWW.Example.com"));
+        assert!(!scope.contains("example.com", "example.com.evil.net"));
+        assert!(!scope.contains("example.com", "cdn.cloudflare.net"));
+    }
+
+    #[test]
+    fn test_include_exclude() {
+        let scope = Scope::new(
+            true,
+            &patterns(&["*.example-cdn.net"]),
+            &patterns(&["*.dev.example.com", " "]),
+        );
+        assert!(scope.contains("example.com", "assets.example-cdn.net"));
+        assert!(!scope.contains("example.com", "api.dev.example.com"));
+        assert!(scope.contains("example.com", "dev.example.com"));
+        // Neither the excluded wildcard parent nor the names expanded from it are resolved
+        assert!(!scope.contains("example.com", "*.dev.example.com"));
+        assert!(!scope.contains("example.com", "*.API.dev.example.com"));
+        assert!(!scope.contains("example.com", "www.dev.example.com"));
+
+        let loose = Scope::new(false, &[], &patterns(&["*.cloudflare.net"]));
+        assert!(loose.contains("example.com", "example.org"));
+        assert!(!loose.contains("example.com", "cdn.cloudflare.net"));
+    }
+}
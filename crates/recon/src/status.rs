@@ -0,0 +1,116 @@
+use async_std_resolver::proto::op::ResponseCode;
+use async_std_resolver::ResolveError;
+use hickory_resolver::error::ResolveErrorKind;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Outcome of resolving the addresses of a domain.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum LookupStatus {
+    /// The domain has addresses.
+    #[default]
+    Resolved,
+    /// The domain does not exist.
+    NxDomain,
+    /// The domain exists, but it has no address.
+    NoData,
+    /// The nameserver failed to answer.
+    ServFail,
+    /// No answer was received in time.
+    Timeout,
+    /// The nameserver refused to answer.
+    Refused,
+    /// Any other failure, ex. a connection error.
+    Error,
+}
+
+impl LookupStatus {
+    pub(crate) fn classify(error: &ResolveError) -> LookupStatus {
+        match error.kind() {
+            ResolveErrorKind::NoRecordsFound { response_code, .. } => match *response_code {
+                ResponseCode::NXDomain => LookupStatus::NxDomain,
+                ResponseCode::NoError => LookupStatus::NoData,
+                ResponseCode::ServFail => LookupStatus::ServFail,
+                ResponseCode::Refused => LookupStatus::Refused,
+                _ => LookupStatus::Error,
+            },
+            ResolveErrorKind::Timeout => LookupStatus::Timeout,
+            _ => LookupStatus::Error,
+        }
+    }
+
+    /// Whether the nameserver answered that the domain has no such record, which is not a failure.
+    pub(crate) fn is_missing_record(&self) -> bool {
+        matches!(self, LookupStatus::NxDomain | LookupStatus::NoData)
+    }
+}
+
+impl Display for LookupStatus {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> anyhow::Result<(), std::fmt::Error> {
+        let status = match self {
+            LookupStatus::Resolved => "RESOLVED",
+            LookupStatus::NxDomain => "NXDOMAIN",
+            LookupStatus::NoData => "NODATA",
+            LookupStatus::ServFail => "SERVFAIL",
+            LookupStatus::Timeout => "TIMEOUT",
+            LookupStatus::Refused => "REFUSED",
+            LookupStatus::Error => "ERROR",
+        };
+        write!(fmt, "{}", status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_std_resolver::proto::op::Query;
+
+    fn no_records(response_code: ResponseCode) -> ResolveError {
+        ResolveError::from(ResolveErrorKind::NoRecordsFound {
+            query: Box::new(Query::default()),
+            soa: None,
+            negative_ttl: None,
+            response_code,
+            trusted: false,
+        })
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            LookupStatus::NxDomain,
+            LookupStatus::classify(&no_records(ResponseCode::NXDomain))
+        );
+        assert_eq!(
+            LookupStatus::NoData,
+            LookupStatus::classify(&no_records(ResponseCode::NoError))
+        );
+        assert_eq!(
+            LookupStatus::ServFail,
+            LookupStatus::classify(&no_records(ResponseCode::ServFail))
+        );
+        assert_eq!(
+            LookupStatus::Refused,
+            LookupStatus::classify(&no_records(ResponseCode::Refused))
+        );
+        assert_eq!(
+            LookupStatus::Timeout,
+            LookupStatus::classify(&ResolveError::from(ResolveErrorKind::Timeout))
+        );
+        assert_eq!(
+            LookupStatus::Error,
+            LookupStatus::classify(&ResolveError::from("Connection refused"))
+        );
+        assert!(!LookupStatus::ServFail.is_missing_record());
+    }
+
+    #[test]
+    fn test_serialize() {
+        assert_eq!(
+            "\"NXDOMAIN\"",
+            serde_json::to_string(&LookupStatus::NxDomain).expect("Error")
+        );
+        assert_eq!("NODATA", LookupStatus::NoData.to_string());
+    }
+}
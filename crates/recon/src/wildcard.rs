@@ -0,0 +1,152 @@
+use crate::dns_client::DnsClient;
+use anyhow::anyhow;
+use futures::StreamExt;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+// Number of random labels resolved under every zone. Wildcards answering with a rotating set of
+// addresses are learned from the union of the answers.
+const PROBES: usize = 3;
+const PROBE_LABEL_LENGTH: usize = 16;
+
+/// What to do with the candidates whose addresses match the wildcard DNS record of their zone.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WildcardDnsMode {
+    /// Drop the candidates expanded from a word list. Names found in certificates are kept and
+    /// flagged.
+    #[default]
+    Drop,
+    /// Keep every candidate and flag the matching ones.
+    Flag,
+}
+
+impl FromStr for WildcardDnsMode {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> anyhow::Result<WildcardDnsMode, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "drop" => Ok(WildcardDnsMode::Drop),
+            "flag" => Ok(WildcardDnsMode::Flag),
+            _ => Err(anyhow!("Unknown wildcard DNS mode: \"{}\"!", input)),
+        }
+    }
+}
+
+/// Addresses answered by the wildcard DNS records, keyed by the zone they were found under.
+#[derive(Debug, Default)]
+pub(crate) struct WildcardZones {
+    answers: HashMap<String, HashSet<String>>,
+}
+
+impl WildcardZones {
+    /// Probe the parent zone of every domain with random labels. Zones where the random labels
+    /// resolve have a wildcard DNS record.
+    pub(crate) async fn detect(
+        dns_client: &DnsClient,
+        domains: &HashSet<String>,
+        number_of_parallel_requests: usize,
+    ) -> WildcardZones {
+        let zones = domains
+            .iter()
+            .filter_map(|domain| parent_zone(domain))
+            .collect::<HashSet<&str>>();
+        let probes = zones
+            .into_iter()
+            .map(|zone| async move { (zone, probe(dns_client, zone).await) })
+            .collect::<Vec<_>>();
+        let answers = futures::stream::iter(probes)
+            .buffer_unordered(number_of_parallel_requests)
+            .filter_map(|(zone, answers)| async move {
+                (!answers.is_empty()).then(|| (zone.to_string(), answers))
+            })
+            .collect::<HashMap<String, HashSet<String>>>()
+            .await;
+        WildcardZones { answers }
+    }
+
+    /// Whether every address of `domain` is answered by the wildcard DNS record of its zone.
+    pub(crate) fn matches(&self, domain: &str, ip_addresses: &[String]) -> bool {
+        match parent_zone(domain).and_then(|zone| self.answers.get(zone)) {
+            Some(answers) => {
+                !ip_addresses.is_empty() && ip_addresses.iter().all(|ip| answers.contains(ip))
+            }
+            None => false,
+        }
+    }
+}
+
+// Lookups failing for any reason are considered as missing, the zone is assumed to have no
+// wildcard in that case
+async fn probe(dns_client: &DnsClient, zone: &str) -> HashSet<String> {
+    let mut answers = HashSet::new();
+    for _ in 0..PROBES {
+        if let Ok(lookup) = dns_client
+            .lookup_ip(&format!("{}.{}", random_label(), zone))
+            .await
+        {
+            answers.extend(lookup.iter().map(|ip| ip.to_string()));
+        }
+    }
+    answers
+}
+
+fn random_label() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(PROBE_LABEL_LENGTH)
+        .map(|c| char::from(c).to_ascii_lowercase())
+        .collect()
+}
+
+// Zone under which a wildcard record would answer for the domain, ex. `example.com` for
+// `www.example.com`
+fn parent_zone(domain: &str) -> Option<&str> {
+    domain
+        .trim_end_matches('.')
+        .split_once('.')
+        .map(|(_, parent)| parent)
+        .filter(|parent| parent.contains('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ips(ips: &[&str]) -> Vec<String> {
+        ips.iter().map(|ip| ip.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parent_zone() {
+        assert_eq!(Some("example.com"), parent_zone("www.example.com"));
+        assert_eq!(Some("dev.example.com"), parent_zone("api.dev.example.com."));
+        assert_eq!(None, parent_zone("example.com"));
+    }
+
+    #[test]
+    fn test_matches() {
+        let zones = WildcardZones {
+            answers: HashMap::from([(
+                "example.com".to_string(),
+                HashSet::from(["192.0.2.1".to_string(), "192.0.2.2".to_string()]),
+            )]),
+        };
+        assert!(zones.matches("random.example.com.", &ips(&["192.0.2.2"])));
+        assert!(zones.matches("random.example.com", &ips(&["192.0.2.1", "192.0.2.2"])));
+        assert!(!zones.matches("www.example.com", &ips(&["192.0.2.1", "198.51.100.7"])));
+        assert!(!zones.matches("www.dev.example.com", &ips(&["192.0.2.1"])));
+        assert!(!zones.matches("www.example.com", &[]));
+    }
+
+    #[test]
+    fn test_random_label() {
+        let label = random_label();
+        assert_eq!(PROBE_LABEL_LENGTH, label.len());
+        assert!(label
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()));
+        assert_ne!(label, random_label());
+    }
+}
@@ -0,0 +1,290 @@
+use crate::report::ReconError;
+use anyhow::anyhow;
+use async_compression::tokio::bufread::{GzipDecoder, ZstdDecoder};
+use futures::{future, stream, Stream, StreamExt};
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tokio::fs::File;
+use tokio::io::{self, AsyncBufRead, AsyncBufReadExt, BufReader, Lines};
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+// Number of words of the lists remembered for skipping the repeated ones. Beyond it the words are
+// not remembered anymore, so that the memory used does not grow with the size of the lists.
+const MAX_SEEN_WORDS: usize = 1 << 20;
+
+// Every tier only holds the words added to the smaller tiers
+const SMALL_WORDS: &str = include_str!("../words/small.txt");
+const MEDIUM_WORDS: &str = include_str!("../words/medium.txt");
+const LARGE_WORDS: &str = include_str!("../words/large.txt");
+
+type WordReader = Lines<Box<dyn AsyncBufRead + Send + Unpin>>;
+
+/// Word lists compiled into the library. Every tier contains the words of the smaller ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordListTier {
+    /// About a hundred of the most common names, ex. `www`, `mail` or `vpn`.
+    Small,
+    /// About six hundred names.
+    Medium,
+    /// About three and a half thousand names, including combinations of environments and
+    /// services, ex. `dev-api` or `portal2`.
+    Large,
+}
+
+impl WordListTier {
+    /// Words of the tier, the most common ones first.
+    pub fn words(&self) -> impl Iterator<Item = &'static str> {
+        let lists: &[&'static str] = match self {
+            WordListTier::Small => &[SMALL_WORDS],
+            WordListTier::Medium => &[SMALL_WORDS, MEDIUM_WORDS],
+            WordListTier::Large => &[SMALL_WORDS, MEDIUM_WORDS, LARGE_WORDS],
+        };
+        lists
+            .iter()
+            .flat_map(|list| list.lines())
+            .map(|word| word.trim())
+            .filter(|word| !word.is_empty())
+    }
+}
+
+impl FromStr for WordListTier {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> anyhow::Result<WordListTier, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "small" => Ok(WordListTier::Small),
+            "medium" => Ok(WordListTier::Medium),
+            "large" => Ok(WordListTier::Large),
+            _ => Err(anyhow!("Unknown word list: \"{}\"!", input)),
+        }
+    }
+}
+
+/// Word lists read line by line every time the words are needed, so that lists of any size can
+/// be used without holding them in memory, besides a bounded number of words for skipping the
+/// repeated ones. The built-in words come first.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct WordLists {
+    tier: Option<WordListTier>,
+    builtin: HashSet<&'static str>,
+    paths: Vec<String>,
+    // Lists which failed to be read, their errors being reported only once for the whole run
+    failed: Arc<Mutex<HashSet<String>>>,
+}
+
+impl WordLists {
+    /// Check that every word list can be opened before any lookup starts.
+    pub(crate) async fn open(
+        tier: Option<WordListTier>,
+        paths: &[String],
+    ) -> anyhow::Result<WordLists, ReconError> {
+        for path in paths {
+            let _ = open(path).await.map_err(|e| word_list_error(path, e))?;
+        }
+        Ok(WordLists {
+            tier,
+            builtin: tier.iter().flat_map(|tier| tier.words()).collect(),
+            paths: paths.to_vec(),
+            failed: Arc::default(),
+        })
+    }
+
+    /// Stream the words of the built-in tier, then the words of every list in the order of the
+    /// lists. Blank lines, the words of the built-in tier and the words repeated in the lists are
+    /// skipped, the latter only within the first `MAX_SEEN_WORDS` distinct words. Words repeated
+    /// after them are looked up again. A list failing to be read ends with an error, unless it
+    /// already failed before, and the next list is read.
+    pub(crate) fn words(&self) -> impl Stream<Item = anyhow::Result<String, ReconError>> + '_ {
+        let builtin = self
+            .tier
+            .iter()
+            .flat_map(|tier| tier.words())
+            .map(|word| Ok(word.to_string()));
+        let mut seen = HashSet::new();
+        let listed = stream::iter(&self.paths)
+            .flat_map(|path| read_words(path))
+            .filter(move |word| {
+                future::ready(match word {
+                    Ok(word) => {
+                        !self.builtin.contains(word.as_str())
+                            && !seen.contains(word)
+                            && (seen.len() >= MAX_SEEN_WORDS || seen.insert(word.clone()))
+                    }
+                    Err(e) => self.first_failure(e),
+                })
+            });
+        stream::iter(builtin).chain(listed)
+    }
+
+    fn first_failure(&self, error: &ReconError) -> bool {
+        match error {
+            ReconError::WordList { path, .. } => self
+                .failed
+                .lock()
+                .map(|mut failed| failed.insert(path.clone()))
+                .unwrap_or(true),
+            _ => true,
+        }
+    }
+}
+
+enum ReadState {
+    // The file is opened on the first read, so that only one list is open at a time
+    Closed,
+    Open(WordReader),
+    Done,
+}
+
+fn read_words(path: &str) -> impl Stream<Item = anyhow::Result<String, ReconError>> + '_ {
+    stream::unfold(ReadState::Closed, move |state| async move {
+        let mut lines = match state {
+            ReadState::Closed => match open(path).await {
+                Ok(lines) => lines,
+                Err(e) => return Some((Err(word_list_error(path, e)), ReadState::Done)),
+            },
+            ReadState::Open(lines) => lines,
+            ReadState::Done => return None,
+        };
+        loop {
+            match lines.next_line().await {
+                Ok(Some(line)) => {
+                    let word = line.trim();
+                    if !word.is_empty() {
+                        return Some((Ok(word.to_string()), ReadState::Open(lines)));
+                    }
+                }
+                Ok(None) => return None,
+                Err(e) => return Some((Err(word_list_error(path, e)), ReadState::Done)),
+            }
+        }
+    })
+}
+
+// Compressed lists are recognized by their magic bytes rather than by their extension
+async fn open(path: &str) -> anyhow::Result<WordReader, io::Error> {
+    let mut reader = BufReader::new(File::open(path).await?);
+    let header = reader.fill_buf().await?;
+    let reader: Box<dyn AsyncBufRead + Send + Unpin> = if header.starts_with(GZIP_MAGIC) {
+        let mut decoder = GzipDecoder::new(reader);
+        decoder.multiple_members(true);
+        Box::new(BufReader::new(decoder))
+    } else if header.starts_with(ZSTD_MAGIC) {
+        Box::new(BufReader::new(ZstdDecoder::new(reader)))
+    } else {
+        Box::new(reader)
+    };
+    Ok(reader.lines())
+}
+
+fn word_list_error(path: &str, error: io::Error) -> ReconError {
+    ReconError::WordList {
+        path: path.to_string(),
+        message: error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_compression::tokio::bufread::{GzipEncoder, ZstdEncoder};
+    use tokio::io::AsyncReadExt;
+
+    async fn write_list(name: &str, content: &[u8]) -> String {
+        let path = std::env::temp_dir().join(format!("{}-{}", std::process::id(), name));
+        tokio::fs::write(&path, content).await.expect("Error");
+        path.to_string_lossy().to_string()
+    }
+
+    async fn words(lists: &WordLists) -> Vec<String> {
+        lists
+            .words()
+            .map(|word| word.expect("Error"))
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_compressed_lists() {
+        let content = b"www\n\n  api \r\nmail\n";
+        let mut gzip = vec![];
+        GzipEncoder::new(&content[..])
+            .read_to_end(&mut gzip)
+            .await
+            .expect("Error");
+        let mut zstd = vec![];
+        ZstdEncoder::new(&content[..])
+            .read_to_end(&mut zstd)
+            .await
+            .expect("Error");
+
+        let paths = vec![
+            write_list("words.txt", content).await,
+            write_list("words.txt.gz", &gzip).await,
+            write_list("words.zst", &zstd).await,
+        ];
+        let lists = WordLists::open(None, &paths).await.expect("Error");
+        for path in &paths {
+            let lists = WordLists::open(None, std::slice::from_ref(path))
+                .await
+                .expect("Error");
+            assert_eq!(vec!["www", "api", "mail"], words(&lists).await);
+        }
+        // The same words in every list are read only once
+        assert_eq!(vec!["www", "api", "mail"], words(&lists).await);
+        for path in paths {
+            tokio::fs::remove_file(path).await.expect("Error");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_missing_list() {
+        let missing = vec![String::from("/does/not/exist.txt")];
+        assert!(matches!(
+            WordLists::open(None, &missing).await,
+            Err(ReconError::WordList { path, .. }) if path == "/does/not/exist.txt"
+        ));
+        let lists = WordLists {
+            paths: missing,
+            ..WordLists::default()
+        };
+        let words = lists.words().collect::<Vec<_>>().await;
+        assert_eq!(1, words.len());
+        assert!(words[0].is_err());
+        // Read again for the next level, the failing list is not reported again
+        assert_eq!(0, lists.words().count().await);
+    }
+
+    #[test]
+    fn test_tiers() {
+        let small = WordListTier::Small.words().collect::<Vec<_>>();
+        let medium = WordListTier::Medium.words().collect::<Vec<_>>();
+        let large = WordListTier::Large.words().collect::<HashSet<_>>();
+        assert_eq!(Some(&"www"), small.first());
+        assert!(small.len() < medium.len() && medium.len() < large.len());
+        assert_eq!(large.len(), WordListTier::Large.words().count());
+        assert!(medium.iter().all(|word| large.contains(word)));
+        assert!(large.iter().all(|word| word
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')));
+        assert_eq!(
+            WordListTier::Medium,
+            WordListTier::from_str("Medium").expect("Error")
+        );
+        assert!(WordListTier::from_str("huge").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_merge_with_tier() {
+        let path = write_list("merged.txt", b"www\ncustom\nmail\n").await;
+        let lists = WordLists::open(Some(WordListTier::Small), std::slice::from_ref(&path))
+            .await
+            .expect("Error");
+        let merged = words(&lists).await;
+        assert_eq!(WordListTier::Small.words().count() + 1, merged.len());
+        assert_eq!(Some(&String::from("custom")), merged.last());
+        tokio::fs::remove_file(path).await.expect("Error");
+    }
+}